@@ -27,6 +27,19 @@ pub struct App {
 
 	pub called_toggle_free_mov: bool,
 
+	// mesh parts (OBJ objects / groups)
+	pub called_select_next_part: bool,
+	pub called_select_prev_part: bool,
+	pub called_toggle_part_visibility: bool,
+
+	// YADE timelines
+	pub called_step_frame_forward: bool,
+	pub called_step_frame_back: bool,
+	pub called_select_next_attribute: bool,
+	pub called_select_prev_attribute: bool,
+	pub called_raise_force_threshold: bool,
+	pub called_lower_force_threshold: bool,
+
 	last_screenshot_instant: Instant,
 }
 
@@ -54,6 +67,17 @@ impl App {
 			called_set_camera_default_orientation: false,
 			called_toggle_free_mov: false,
 
+			called_select_next_part: false,
+			called_select_prev_part: false,
+			called_toggle_part_visibility: false,
+
+			called_step_frame_forward: false,
+			called_step_frame_back: false,
+			called_select_next_attribute: false,
+			called_select_prev_attribute: false,
+			called_raise_force_threshold: false,
+			called_lower_force_threshold: false,
+
 			last_screenshot_instant: Instant::now() - App::SCREENDUMP_DELAY_DURATION,
 		}
 	}
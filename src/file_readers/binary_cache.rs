@@ -0,0 +1,537 @@
+use std::{fs, path::Path, time::UNIX_EPOCH};
+
+use crate::{maths::*, rendering::mesh::{Material, Mesh, MeshPart}};
+
+use super::{obj_reader::ReaderError, yade_dem_reader::{Ball, Cuboid, Cylinder, Tri, Wall, YadeDemData}, yade_timeline::YadeFrame};
+
+
+pub const CACHE_MAGIC: &[u8; 8] = b"PEEKDEM\0";
+// bump it when the layout changes, caches of other versions are parsed again
+const CACHE_VERSION: u32 = 2;
+
+const KIND_MESH: u8 = 0;
+const KIND_FRAMES: u8 = 1;
+
+// a tri material that is None
+const NO_MATERIAL: u32 = u32::MAX;
+
+
+// what a file is parsed into, in file units
+pub enum CachedScene {
+	Mesh(Box<Mesh>),
+	// DEM snapshots, a single one for most formats
+	Frames(Vec<YadeFrame>),
+}
+
+// modification time (seconds and nanoseconds since the epoch) and size of a file, None if it can't be read
+pub type FileStamp = Option<(u64, u32, u64)>;
+
+pub fn file_stamp(path: &str) -> FileStamp {
+	let metadata = fs::metadata(path).ok()?;
+	let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+	Some((modified.as_secs(), modified.subsec_nanos(), metadata.len()))
+}
+
+// what a cache was made from, it's only used while none of it changed
+#[derive(Clone)]
+pub struct CacheHeader {
+	// what changes how the file is parsed (the format and its options)
+	pub key: String,
+	// the source file first, then the files it includes (the material libraries of an OBJ)
+	pub sources: Vec<(String, FileStamp)>,
+}
+
+impl CacheHeader {
+	// 'source_stamp' is taken before the source is parsed, so a change while it's parsed is not missed
+	pub fn new(key: &str, source: &str, source_stamp: FileStamp, scene: &CachedScene) -> Self {
+		let includes = scene.includes().into_iter().map(|path| {
+			let stamp = file_stamp(&path);
+			(path, stamp)
+		});
+
+		Self { key: key.to_owned(), sources: std::iter::once((source.to_owned(), source_stamp)).chain(includes).collect() }
+	}
+
+	// the source is looked for at 'source', the cache can be read from another directory than the one it was written from
+	fn is_up_to_date(&self, source: &str, key: &str) -> bool {
+		let Some(((_, source_stamp), includes)) = self.sources.split_first() else { return false };
+
+		self.key == key
+			&& source_stamp.is_some() && *source_stamp == file_stamp(source)
+			&& includes.iter().all(|(path, stamp)| *stamp == file_stamp(path))
+	}
+}
+
+impl CachedScene {
+	// other files read with the source, their changes change the scene too
+	pub fn includes(&self) -> Vec<String> {
+		match self {
+			CachedScene::Mesh(mesh) => mesh.mtl_paths.clone(),
+			CachedScene::Frames(_) => vec![],
+		}
+	}
+}
+
+// "dir/.name.ext.peekdem", hidden so it's not listed as a snapshot of a sequence
+pub fn cache_path_for(source: &str) -> String {
+	let source_path = Path::new(source);
+	let file_name = source_path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+
+	source_path.with_file_name(format!(".{}.peekdem", file_name)).to_string_lossy().into_owned()
+}
+
+// the cache of 'source' if it was written with the same 'key' and the source and its includes have the same modification time
+// and size as then, otherwise 'source' is parsed with 'parse' and its cache is written
+pub fn read_with_cache(source: &str, key: &str, parse: impl FnOnce() -> Result<CachedScene, ReaderError>) -> Result<CachedScene, ReaderError> {
+	let cache_path = cache_path_for(source);
+
+	// an old or broken cache is just parsed again
+	if let Ok((header, scene)) = read_cache(&cache_path, source) {
+		if header.is_up_to_date(source, key) { return Ok(scene) }
+	}
+
+	let source_stamp = file_stamp(source);
+	let scene = parse()?;
+
+	// a read-only directory or a full disk only makes the next load slower
+	let _ = write_cache_file(&cache_path, &CacheHeader::new(key, source, source_stamp, &scene), &scene);
+
+	Ok(scene)
+}
+
+// "PEEKDEM\0", the version (u32), the key, the stamps of the sources, the kind (u8) and the mesh or the frames,
+// numbers are little endian and arrays start with their length (u64)
+pub fn write_cache_file(path: &str, header: &CacheHeader, scene: &CachedScene) -> Result<(), ReaderError> {
+	let mut writer = CacheWriter { bytes: Vec::new() };
+
+	writer.bytes.extend_from_slice(CACHE_MAGIC);
+	writer.put_u32(CACHE_VERSION);
+	writer.put_str(&header.key);
+
+	writer.put_len(header.sources.len());
+	for (source, stamp) in &header.sources {
+		writer.put_str(source);
+		writer.put_u8(stamp.is_some() as u8);
+		let (secs, nanos, len) = stamp.unwrap_or_default();
+		writer.put_u64(secs);
+		writer.put_u32(nanos);
+		writer.put_u64(len);
+	}
+
+	match scene {
+		CachedScene::Mesh(mesh) => {
+			writer.put_u8(KIND_MESH);
+			writer.put_mesh(mesh);
+		}
+		CachedScene::Frames(frames) => {
+			writer.put_u8(KIND_FRAMES);
+			writer.put_len(frames.len());
+			for frame in frames {
+				writer.put_u8(frame.iteration.is_some() as u8);
+				writer.put_u64(frame.iteration.unwrap_or(0));
+				writer.put_dem_data(&frame.data);
+			}
+		}
+	}
+
+	fs::write(path, &writer.bytes).map_err(|err| ReaderError::from_io_error(err, path))
+}
+
+// a cache opened directly, its frames are named after it
+pub fn read_cache_file(path: &str) -> Result<CachedScene, ReaderError> {
+	read_cache(path, path).map(|(_, scene)| scene)
+}
+
+// and the header it was written with
+pub fn read_cache_file_with_header(path: &str) -> Result<(CacheHeader, CachedScene), ReaderError> {
+	read_cache(path, path)
+}
+
+fn read_cache(path: &str, frames_path: &str) -> Result<(CacheHeader, CachedScene), ReaderError> {
+	let bytes = fs::read(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+	let mut reader = CacheReader { bytes: &bytes, pos: 0 };
+
+	if !bytes.starts_with(CACHE_MAGIC) {
+		return Err(reader.error("not a peekdem cache, it should start with 'PEEKDEM'".to_owned()));
+	}
+	reader.pos = CACHE_MAGIC.len();
+
+	let version = reader.get_u32()?;
+	if version != CACHE_VERSION {
+		return Err(reader.error(format!("the cache is version {}, this peekdem reads version {}, convert the source file again", version, CACHE_VERSION)));
+	}
+
+	let key = reader.get_str()?;
+
+	let sources_count = reader.get_len(8 + 1 + 8 + 4 + 8)?;
+	let mut sources = Vec::with_capacity(sources_count);
+	for _ in 0..sources_count {
+		let source = reader.get_str()?;
+		let has_stamp = reader.get_u8()? != 0;
+		let stamp = (reader.get_u64()?, reader.get_u32()?, reader.get_u64()?);
+		sources.push((source, has_stamp.then_some(stamp)));
+	}
+	let header = CacheHeader { key, sources };
+
+	let scene = match reader.get_u8()? {
+		KIND_MESH => CachedScene::Mesh(Box::new(reader.get_mesh()?)),
+		KIND_FRAMES => {
+			let frames_count = reader.get_len(1 + 8)?;
+			if frames_count == 0 {
+				return Err(reader.error("the cache has no frames".to_owned()));
+			}
+
+			let mut frames = Vec::with_capacity(frames_count);
+			for _ in 0..frames_count {
+				let has_iteration = reader.get_u8()? != 0;
+				let iteration = reader.get_u64()?;
+				let data = reader.get_dem_data()?;
+				frames.push(YadeFrame { path: frames_path.to_owned(), iteration: has_iteration.then_some(iteration), data });
+			}
+			CachedScene::Frames(frames)
+		}
+		kind => return Err(reader.error(format!("unknown cache kind {}", kind))),
+	};
+
+	if reader.pos != bytes.len() {
+		return Err(reader.error("the cache has extra bytes at the end".to_owned()));
+	}
+
+	Ok((header, scene))
+}
+
+
+struct CacheWriter {
+	bytes: Vec<u8>,
+}
+
+impl CacheWriter {
+	fn put_u8(&mut self, value: u8) {
+		self.bytes.push(value);
+	}
+
+	fn put_u32(&mut self, value: u32) {
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	fn put_u64(&mut self, value: u64) {
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	fn put_len(&mut self, len: usize) {
+		self.put_u64(len as u64);
+	}
+
+	fn put_str(&mut self, value: &str) {
+		self.put_len(value.len());
+		self.bytes.extend_from_slice(value.as_bytes());
+	}
+
+	fn put_f32s(&mut self, values: &[f32]) {
+		self.put_len(values.len());
+		for value in values {
+			self.bytes.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+
+	fn put_u32s(&mut self, values: &[u32]) {
+		self.put_len(values.len());
+		for value in values {
+			self.bytes.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+
+	fn put_u64s(&mut self, values: &[u64]) {
+		self.put_len(values.len());
+		for value in values {
+			self.bytes.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+
+	fn put_mesh(&mut self, mesh: &Mesh) {
+		self.put_f32s(&mesh.verts);
+		self.put_u32s(&mesh.tris_indices);
+		self.put_f32s(&mesh.normals);
+		self.put_u32s(&mesh.normal_indices);
+
+		self.put_len(mesh.parts.len());
+		for part in &mesh.parts {
+			self.put_str(&part.name);
+			self.put_len(part.tris.start);
+			self.put_len(part.tris.end);
+		}
+
+		self.put_len(mesh.materials.len());
+		for material in &mesh.materials {
+			self.put_str(&material.name);
+			self.put_f32s(&[material.diffuse.x, material.diffuse.y, material.diffuse.z]);
+		}
+
+		let tri_materials: Vec<u32> = mesh.tri_materials.iter().map(|material| material.unwrap_or(NO_MATERIAL)).collect();
+		self.put_u32s(&tri_materials);
+
+		self.put_f32s(&mesh.face_normals);
+		self.put_u32s(&mesh.tri_smoothing_groups);
+		self.put_f32s(&mesh.vert_colors);
+
+		self.put_len(mesh.mtl_paths.len());
+		for mtl_path in &mesh.mtl_paths {
+			self.put_str(mtl_path);
+		}
+	}
+
+	// every kind of body is a few arrays, one value (or 3 for a position) per body
+	fn put_dem_data(&mut self, data: &YadeDemData) {
+		self.put_len(data.attribute_names.len());
+		for name in &data.attribute_names {
+			self.put_str(name);
+		}
+
+		let positions: Vec<f32> = data.balls.iter().flat_map(|ball| [ball.pos.x, ball.pos.y, ball.pos.z]).collect();
+		let radii: Vec<f32> = data.balls.iter().map(|ball| ball.rad).collect();
+		let ids: Vec<u64> = data.balls.iter().map(|ball| ball.id as u64).collect();
+		self.put_f32s(&positions);
+		self.put_f32s(&radii);
+		self.put_u64s(&ids);
+		self.put_attributes(data.balls.iter().map(|ball| &ball.attributes));
+
+		let points: Vec<f32> = data.tris.iter().flat_map(|tri| [tri.p0, tri.p1, tri.p2]).flat_map(|point| [point.x, point.y, point.z]).collect();
+		self.put_f32s(&points);
+		self.put_attributes(data.tris.iter().map(|tri| &tri.attributes));
+
+		let cuboids: Vec<f32> = data.cuboids.iter().flat_map(|cuboid| [cuboid.center, cuboid.extents]).flat_map(|vec| [vec.x, vec.y, vec.z]).collect();
+		self.put_f32s(&cuboids);
+
+		let wall_positions: Vec<f32> = data.walls.iter().flat_map(|wall| [wall.pos.x, wall.pos.y, wall.pos.z]).collect();
+		let wall_axes: Vec<u32> = data.walls.iter().map(|wall| wall.axis as u32).collect();
+		self.put_f32s(&wall_positions);
+		self.put_u32s(&wall_axes);
+
+		let cylinders: Vec<f32> = data.cylinders.iter().flat_map(|cylinder| [cylinder.p0.x, cylinder.p0.y, cylinder.p0.z, cylinder.p1.x, cylinder.p1.y, cylinder.p1.z, cylinder.rad]).collect();
+		self.put_f32s(&cylinders);
+	}
+
+	// how many attributes each body has and all of them, one body after the other
+	fn put_attributes<'a>(&mut self, attributes: impl Iterator<Item = &'a Vec<Float>> + Clone) {
+		let counts: Vec<u32> = attributes.clone().map(|attributes| attributes.len() as u32).collect();
+		let values: Vec<f32> = attributes.flatten().copied().collect();
+		self.put_u32s(&counts);
+		self.put_f32s(&values);
+	}
+}
+
+
+struct CacheReader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> CacheReader<'a> {
+	fn error(&self, msg: String) -> ReaderError {
+		ReaderError::bad_format(format!("{} (at byte {} of the cache)", msg, self.pos), 1, 1)
+	}
+
+	fn take(&mut self, len: usize) -> Result<&'a [u8], ReaderError> {
+		if len > self.bytes.len() - self.pos {
+			return Err(self.error("unexpected end of the cache".to_owned()));
+		}
+
+		let taken = &self.bytes[self.pos..self.pos + len];
+		self.pos += len;
+		Ok(taken)
+	}
+
+	fn get_u8(&mut self) -> Result<u8, ReaderError> {
+		Ok(self.take(1)?[0])
+	}
+
+	fn get_u32(&mut self) -> Result<u32, ReaderError> {
+		Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+	}
+
+	fn get_u64(&mut self) -> Result<u64, ReaderError> {
+		Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+	}
+
+	// a length of things at least 'item_size' bytes long each, checked against what's left so a broken cache doesn't allocate too much
+	fn get_len(&mut self, item_size: usize) -> Result<usize, ReaderError> {
+		let len = self.get_u64()?;
+		let left = (self.bytes.len() - self.pos) as u64;
+		if len.saturating_mul(item_size as u64) > left {
+			return Err(self.error(format!("a length of {} is more than what's left of the cache", len)));
+		}
+
+		Ok(len as usize)
+	}
+
+	fn get_str(&mut self) -> Result<String, ReaderError> {
+		let len = self.get_len(1)?;
+		let bytes = self.take(len)?;
+		String::from_utf8(bytes.to_vec()).map_err(|_| self.error("a name in the cache is not UTF-8".to_owned()))
+	}
+
+	fn get_f32s(&mut self) -> Result<Vec<f32>, ReaderError> {
+		let len = self.get_len(4)?;
+		Ok(self.take(len * 4)?.chunks_exact(4).map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap())).collect())
+	}
+
+	fn get_u32s(&mut self) -> Result<Vec<u32>, ReaderError> {
+		let len = self.get_len(4)?;
+		Ok(self.take(len * 4)?.chunks_exact(4).map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap())).collect())
+	}
+
+	fn get_u64s(&mut self) -> Result<Vec<u64>, ReaderError> {
+		let len = self.get_len(8)?;
+		Ok(self.take(len * 8)?.chunks_exact(8).map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap())).collect())
+	}
+
+	// 'values' has 'per_item' values for each of 'count' things
+	fn check_len(&self, values_len: usize, count: usize, per_item: usize, what: &str) -> Result<(), ReaderError> {
+		if values_len == count * per_item { return Ok(()) }
+
+		Err(self.error(format!("the cache has {} values for {} {}, expected {}", values_len, count, what, count * per_item)))
+	}
+
+	fn get_mesh(&mut self) -> Result<Mesh, ReaderError> {
+		let verts = self.get_f32s()?;
+		let tris_indices = self.get_u32s()?;
+		let normals = self.get_f32s()?;
+		let normal_indices = self.get_u32s()?;
+
+		let parts_count = self.get_len(8 + 8 + 8)?;
+		let mut parts = Vec::with_capacity(parts_count);
+		for _ in 0..parts_count {
+			let name = self.get_str()?;
+			let tris = self.get_u64()? as usize..self.get_u64()? as usize;
+			parts.push(MeshPart { name, tris });
+		}
+
+		let materials_count = self.get_len(8 + 8)?;
+		let mut materials = Vec::with_capacity(materials_count);
+		for _ in 0..materials_count {
+			let name = self.get_str()?;
+			let diffuse = self.get_f32s()?;
+			self.check_len(diffuse.len(), 1, 3, "material color")?;
+			materials.push(Material { name, diffuse: Vec3::new(diffuse[0], diffuse[1], diffuse[2]) });
+		}
+
+		let tri_materials: Vec<Option<u32>> = self.get_u32s()?.into_iter().map(|material| (material != NO_MATERIAL).then_some(material)).collect();
+
+		let face_normals = self.get_f32s()?;
+		let tri_smoothing_groups = self.get_u32s()?;
+		let vert_colors = self.get_f32s()?;
+
+		let mtl_paths_count = self.get_len(8)?;
+		let mut mtl_paths = Vec::with_capacity(mtl_paths_count);
+		for _ in 0..mtl_paths_count {
+			mtl_paths.push(self.get_str()?);
+		}
+
+		// the renderer indexes these arrays with each other, a broken cache would panic there
+		let verts_count = verts.len() / 3;
+		let normals_count = normals.len() / 3;
+		let tris_count = tris_indices.len() / 3;
+		self.check_len(verts.len(), verts_count, 3, "vertices")?;
+		self.check_len(normals.len(), normals_count, 3, "normals")?;
+		self.check_len(tris_indices.len(), tris_count, 3, "triangles")?;
+		self.check_len(normal_indices.len(), tris_count, 3, "triangle normal indices")?;
+		self.check_len(face_normals.len(), tris_count, 3, "face normals")?;
+		// these are empty when the mesh doesn't have them
+		if !tri_materials.is_empty() {
+			self.check_len(tri_materials.len(), tris_count, 1, "triangle materials")?;
+		}
+		if !tri_smoothing_groups.is_empty() {
+			self.check_len(tri_smoothing_groups.len(), tris_count, 1, "triangle smoothing groups")?;
+		}
+		if !vert_colors.is_empty() {
+			self.check_len(vert_colors.len(), verts_count, 3, "vertex colors")?;
+		}
+
+		if tris_indices.iter().any(|&index| index as usize >= verts_count) {
+			return Err(self.error("the cache has a triangle index out of range".to_owned()));
+		}
+		if normal_indices.iter().any(|&index| index as usize >= normals_count) {
+			return Err(self.error("the cache has a normal index out of range".to_owned()));
+		}
+		if tri_materials.iter().flatten().any(|&material| material as usize >= materials.len()) {
+			return Err(self.error("the cache has a material index out of range".to_owned()));
+		}
+		if parts.iter().any(|part| part.tris.start > part.tris.end || part.tris.end > tris_count) {
+			return Err(self.error("the cache has a part with triangles out of range".to_owned()));
+		}
+
+		Ok(Mesh { verts, tris_indices, normals, normal_indices, parts, materials, tri_materials, face_normals, tri_smoothing_groups, vert_colors, mtl_paths })
+	}
+
+	fn get_dem_data(&mut self) -> Result<YadeDemData, ReaderError> {
+		let mut data = YadeDemData::empty();
+
+		let names_count = self.get_len(8)?;
+		for _ in 0..names_count {
+			data.attribute_names.push(self.get_str()?);
+		}
+
+		let positions = self.get_f32s()?;
+		let radii = self.get_f32s()?;
+		let ids = self.get_u64s()?;
+		let balls_count = radii.len();
+		self.check_len(positions.len(), balls_count, 3, "ball positions")?;
+		self.check_len(ids.len(), balls_count, 1, "ball ids")?;
+		let attributes = self.get_attributes(balls_count)?;
+
+		data.balls = positions.chunks_exact(3).zip(radii).zip(ids).zip(attributes)
+			.map(|(((pos, rad), id), attributes)| Ball { pos: Vec3::new(pos[0], pos[1], pos[2]), rad, id: id as usize, attributes })
+			.collect();
+
+		let points = self.get_f32s()?;
+		let tris_count = points.len() / 9;
+		self.check_len(points.len(), tris_count, 9, "triangle points")?;
+		let attributes = self.get_attributes(tris_count)?;
+
+		data.tris = points.chunks_exact(9).zip(attributes)
+			.map(|(points, attributes)| Tri {
+				p0: Vec3::new(points[0], points[1], points[2]),
+				p1: Vec3::new(points[3], points[4], points[5]),
+				p2: Vec3::new(points[6], points[7], points[8]),
+				attributes,
+			})
+			.collect();
+
+		let cuboids = self.get_f32s()?;
+		self.check_len(cuboids.len(), cuboids.len() / 6, 6, "boxes")?;
+		data.cuboids = cuboids.chunks_exact(6)
+			.map(|cuboid| Cuboid { center: Vec3::new(cuboid[0], cuboid[1], cuboid[2]), extents: Vec3::new(cuboid[3], cuboid[4], cuboid[5]) })
+			.collect();
+
+		let wall_positions = self.get_f32s()?;
+		let wall_axes = self.get_u32s()?;
+		self.check_len(wall_positions.len(), wall_axes.len(), 3, "wall positions")?;
+		if wall_axes.iter().any(|&axis| axis > 2) {
+			return Err(self.error("the axis of a wall should be 0, 1 or 2".to_owned()));
+		}
+		data.walls = wall_positions.chunks_exact(3).zip(wall_axes)
+			.map(|(pos, axis)| Wall { pos: Vec3::new(pos[0], pos[1], pos[2]), axis: axis as usize })
+			.collect();
+
+		let cylinders = self.get_f32s()?;
+		self.check_len(cylinders.len(), cylinders.len() / 7, 7, "cylinders")?;
+		data.cylinders = cylinders.chunks_exact(7)
+			.map(|cylinder| Cylinder { p0: Vec3::new(cylinder[0], cylinder[1], cylinder[2]), p1: Vec3::new(cylinder[3], cylinder[4], cylinder[5]), rad: cylinder[6] })
+			.collect();
+
+		Ok(data)
+	}
+
+	// the attributes of 'count' bodies, see 'CacheWriter::put_attributes'
+	fn get_attributes(&mut self, count: usize) -> Result<Vec<Vec<Float>>, ReaderError> {
+		let counts = self.get_u32s()?;
+		let values = self.get_f32s()?;
+		self.check_len(counts.len(), count, 1, "attribute counts")?;
+
+		let values_count: usize = counts.iter().map(|&count| count as usize).sum();
+		self.check_len(values.len(), values_count, 1, "attributes")?;
+
+		let mut values = values.into_iter();
+		Ok(counts.iter().map(|&count| values.by_ref().take(count as usize).collect()).collect())
+	}
+}
@@ -0,0 +1,175 @@
+use crate::maths::*;
+
+use super::yade_dem_reader::YadeDemData;
+
+
+// YADE exports are Z up and tiny (meters), this makes them look good from the default camera
+pub const DEFAULT_YADE_SCALE: f32 = 15.0;
+
+// in 'auto' scale, the scene's bounding box is scaled to fit a sphere of this radius around the origin,
+// which is half the distance from the default orbital camera (16 units away)
+const AUTO_SCALE_FIT_RADIUS: f32 = 8.0;
+
+
+#[derive(Clone, Copy)]
+pub enum UpAxis {
+	X,
+	Y,
+	Z,
+}
+
+#[derive(Clone, Copy)]
+pub enum Handedness {
+	Right,
+	Left,
+}
+
+#[derive(Clone, Copy)]
+pub enum UnitScale {
+	Fixed(f32),
+	// centers the scene and scales it to fit the default camera
+	Auto,
+}
+
+// how the coordinates of an input file are laid out
+#[derive(Clone, Copy)]
+pub struct CoordinateConvention {
+	pub up_axis: UpAxis,
+	pub handedness: Handedness,
+	pub scale: UnitScale,
+}
+
+impl Default for CoordinateConvention {
+	// YADE's
+	fn default() -> Self {
+		Self { up_axis: UpAxis::Z, handedness: Handedness::Right, scale: UnitScale::Fixed(DEFAULT_YADE_SCALE) }
+	}
+}
+
+impl UpAxis {
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name.to_ascii_lowercase().as_str() {
+			"x" => Some(UpAxis::X),
+			"y" => Some(UpAxis::Y),
+			"z" => Some(UpAxis::Z),
+			_ => None,
+		}
+	}
+}
+
+impl Handedness {
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name.to_ascii_lowercase().as_str() {
+			"right" | "r" => Some(Handedness::Right),
+			"left"  | "l" => Some(Handedness::Left),
+			_ => None,
+		}
+	}
+}
+
+impl UnitScale {
+	pub fn from_name(name: &str) -> Option<Self> {
+		if name.eq_ignore_ascii_case("auto") { return Some(UnitScale::Auto) }
+
+		name.parse::<f32>().ok().filter(|scale| *scale > 0.0).map(UnitScale::Fixed)
+	}
+}
+
+
+// converts file coordinates into world coordinates (Y up, left handed) and back
+#[derive(Clone, Copy)]
+pub struct WorldTransform {
+	// world axis i is file axis 'axes[i]' times 'signs[i]'
+	axes: [usize; 3],
+	signs: [f32; 3],
+	// in file units, subtracted before scaling
+	offset: Vec3,
+	pub scale: f32,
+}
+
+impl WorldTransform {
+	// 'data' is in file units, it's only looked at for the 'auto' scale
+	pub fn new(convention: &CoordinateConvention, data: &YadeDemData) -> Self {
+
+		// the world is left handed, so right handed files get their depth axis mirrored
+		let mirror = match convention.handedness {
+			Handedness::Right => -1.0,
+			Handedness::Left  =>  1.0,
+		};
+
+		let (axes, signs) = match convention.up_axis {
+			UpAxis::X => ([1, 0, 2], [1.0, 1.0, -mirror]),
+			UpAxis::Y => ([0, 1, 2], [1.0, 1.0,  mirror]),
+			UpAxis::Z => ([0, 2, 1], [1.0, 1.0, -mirror]),
+		};
+
+		let (offset, scale) = match convention.scale {
+			UnitScale::Fixed(scale) => (Vec3::zero(), scale),
+			UnitScale::Auto => Self::find_auto_offset_and_scale(data),
+		};
+
+		Self { axes, signs, offset, scale }
+	}
+
+	pub fn file_to_world(&self, file_pos: &Vec3) -> Vec3 {
+		let centered = file_pos - &self.offset;
+		let file = [centered.x, centered.y, centered.z];
+
+		Vec3::new(
+			file[self.axes[0]] * self.signs[0],
+			file[self.axes[1]] * self.signs[1],
+			file[self.axes[2]] * self.signs[2],
+		).scale(self.scale)
+	}
+
+	pub fn world_to_file_units(&self, world_pos: &Vec3) -> Vec3 {
+		let world = [world_pos.x, world_pos.y, world_pos.z];
+
+		let mut file = [0.0; 3];
+		for world_axis in 0..3 {
+			file[self.axes[world_axis]] = world[world_axis] * self.signs[world_axis] / self.scale;
+		}
+
+		Vec3::new(file[0], file[1], file[2]).add_vec(&self.offset)
+	}
+
+	pub fn apply(&self, data: &mut YadeDemData) {
+		for ball in data.balls.iter_mut() {
+			ball.pos = self.file_to_world(&ball.pos);
+			ball.rad *= self.scale;
+		}
+
+		for tri in data.tris.iter_mut() {
+			tri.p0 = self.file_to_world(&tri.p0);
+			tri.p1 = self.file_to_world(&tri.p1);
+			tri.p2 = self.file_to_world(&tri.p2);
+		}
+
+		for cuboid in data.cuboids.iter_mut() {
+			cuboid.center = self.file_to_world(&cuboid.center);
+			let extents = [cuboid.extents.x, cuboid.extents.y, cuboid.extents.z];
+			cuboid.extents = Vec3::new(extents[self.axes[0]], extents[self.axes[1]], extents[self.axes[2]]).scale(self.scale);
+		}
+
+		for wall in data.walls.iter_mut() {
+			wall.pos = self.file_to_world(&wall.pos);
+			wall.axis = self.axes.iter().position(|&file_axis| file_axis == wall.axis).unwrap();
+		}
+
+		for cylinder in data.cylinders.iter_mut() {
+			cylinder.p0 = self.file_to_world(&cylinder.p0);
+			cylinder.p1 = self.file_to_world(&cylinder.p1);
+			cylinder.rad *= self.scale;
+		}
+	}
+
+	fn find_auto_offset_and_scale(data: &YadeDemData) -> (Vec3, f32) {
+		let Some((min, max)) = data.get_bounds() else { return (Vec3::zero(), 1.0) };
+
+		let center = (min + max) / 2.0;
+		let half_diagonal = (max - min).magnitude() / 2.0;
+		let scale = if half_diagonal > 0.0 { AUTO_SCALE_FIT_RADIUS / half_diagonal } else { 1.0 };
+
+		(center, scale)
+	}
+}
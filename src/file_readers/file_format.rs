@@ -0,0 +1,180 @@
+use std::{fs::File, io::Read};
+
+use crate::rendering::mesh::Mesh;
+
+use super::{binary_cache::CACHE_MAGIC, obj_reader::{read_mesh_from_obj_file, ReaderError}, off_reader::read_mesh_from_off_file, ply_reader::read_mesh_from_ply_file, stl_reader::read_mesh_from_stl_file};
+
+
+pub type MeshReader = fn(&str) -> Result<Mesh, ReaderError>;
+
+// how many bytes from the start of the file are looked at to guess its format
+const SNIFF_LEN: usize = 4096;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileFormat {
+	Obj,
+	Stl,
+	Ply,
+	Off,
+	Yade,
+	Lammps,
+	Spheres,
+	Vtk,
+	// peekdem's binary cache of any of the others
+	Cache,
+}
+
+impl FileFormat {
+	// in the order they are tried when sniffing, the most specific first
+	pub const ALL: [FileFormat; 9] = [FileFormat::Cache, FileFormat::Ply, FileFormat::Vtk, FileFormat::Off, FileFormat::Stl, FileFormat::Obj, FileFormat::Lammps, FileFormat::Yade, FileFormat::Spheres];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			FileFormat::Obj  => "obj",
+			FileFormat::Stl  => "stl",
+			FileFormat::Ply  => "ply",
+			FileFormat::Off  => "off",
+			FileFormat::Yade => "yade",
+			FileFormat::Lammps => "lammps",
+			FileFormat::Spheres => "spheres",
+			FileFormat::Vtk => "vtk",
+			FileFormat::Cache => "cache",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<FileFormat> {
+		Self::ALL.into_iter().find(|format| format.name().eq_ignore_ascii_case(name))
+	}
+
+	// "cache, ply, vtk, off, stl, obj, lammps, yade, spheres"
+	pub fn list_names() -> String {
+		Self::ALL.map(|format| format.name()).join(", ")
+	}
+
+	// None for formats that are not read as a mesh
+	pub fn mesh_reader(&self) -> Option<MeshReader> {
+		match self {
+			FileFormat::Obj  => Some(read_mesh_from_obj_file),
+			FileFormat::Stl  => Some(read_mesh_from_stl_file),
+			FileFormat::Ply  => Some(read_mesh_from_ply_file),
+			FileFormat::Off  => Some(read_mesh_from_off_file),
+			FileFormat::Yade | FileFormat::Lammps | FileFormat::Spheres | FileFormat::Vtk | FileFormat::Cache => None,
+		}
+	}
+
+	// guesses the format by looking at the start of the file, the extension is not used
+	pub fn detect(path: &str) -> Result<FileFormat, ReaderError> {
+		let mut file = File::open(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+		let file_len = file.metadata().map_err(|err| ReaderError::from_io_error(err, path))?.len();
+
+		let mut head = Vec::with_capacity(SNIFF_LEN);
+		file.by_ref().take(SNIFF_LEN as u64).read_to_end(&mut head).map_err(|err| ReaderError::from_io_error(err, path))?;
+
+		Self::ALL.into_iter()
+			.find(|format| format.sniff(&head, file_len))
+			.ok_or_else(|| ReaderError::UnknownFormat(path.to_owned()))
+	}
+
+	fn sniff(&self, head: &[u8], file_len: u64) -> bool {
+		match self {
+			FileFormat::Obj  => sniff_obj(head),
+			FileFormat::Stl  => sniff_stl(head, file_len),
+			FileFormat::Ply  => head.starts_with(b"ply\n") || head.starts_with(b"ply\r\n"),
+			FileFormat::Off  => sniff_off(head),
+			FileFormat::Yade => sniff_yade(head),
+			FileFormat::Lammps => head_lines(head).next() == Some("ITEM: TIMESTEP"),
+			FileFormat::Spheres => sniff_spheres(head),
+			FileFormat::Vtk => head.starts_with(b"# vtk DataFile") || head_lines(head).any(|line| line.starts_with("<VTKFile")),
+			FileFormat::Cache => head.starts_with(CACHE_MAGIC),
+		}
+	}
+}
+
+// the last line may be cut in half by the sniff length, so it's left out unless it's all there is
+fn head_lines(head: &[u8]) -> impl Iterator<Item = &str> {
+	let text = match std::str::from_utf8(head) {
+		Ok(text) => text,
+		Err(err) => std::str::from_utf8(&head[..err.valid_up_to()]).unwrap(),
+	};
+
+	let lines_count = text.lines().count();
+	let is_cut = head.len() == SNIFF_LEN && lines_count > 1;
+	text.lines().take(if is_cut { lines_count - 1 } else { lines_count })
+		.map(|line| line.trim())
+		.filter(|line| !line.is_empty())
+}
+
+fn sniff_obj(head: &[u8]) -> bool {
+	const KEYWORDS: [&str; 11] = ["v", "vn", "vt", "vp", "f", "l", "o", "g", "s", "mtllib", "usemtl"];
+
+	let first_line = head_lines(head).find(|line| !line.starts_with('#'));
+	let keyword = first_line.and_then(|line| line.split_whitespace().next());
+
+	keyword.is_some_and(|keyword| KEYWORDS.contains(&keyword))
+}
+
+// binary STL has an 80 byte header, a u32 triangle count and 50 bytes per triangle, it can start with "solid" too
+fn sniff_stl(head: &[u8], file_len: u64) -> bool {
+	if head.len() >= 84 {
+		let tris_count = u32::from_le_bytes([head[80], head[81], head[82], head[83]]) as u64;
+		if file_len == 84 + tris_count * 50 { return true }
+	}
+
+	let mut lines = head_lines(head);
+	let starts_with_solid = lines.next().is_some_and(|line| line.starts_with("solid"));
+	starts_with_solid && lines.next().is_none_or(|line| line.starts_with("facet") || line.starts_with("endsolid"))
+}
+
+fn sniff_off(head: &[u8]) -> bool {
+	let first_line = head_lines(head).find(|line| !line.starts_with('#'));
+	let keyword = first_line.and_then(|line| line.split_whitespace().next());
+
+	matches!(keyword, Some("OFF" | "COFF" | "NOFF" | "CNOFF" | "NCOFF"))
+}
+
+// comma separated numbers, starting with the shape id (so sphere lists like "0.5, 0.1, 0.2, 0.05" are not YADE),
+// without a "# type, ..." header row each line has the values of its shape, so "1, 2, 3, 0.5" is a sphere list
+fn sniff_yade(head: &[u8]) -> bool {
+	let has_header_row = head_lines(head).any(|line| line.strip_prefix('#').is_some_and(|header| header.split(',').next().is_some_and(|first| first.trim().eq_ignore_ascii_case("type"))));
+
+	let mut lines = head_lines(head).filter(|line| !line.starts_with('#') && !line.starts_with("//")).peekable();
+	if lines.peek().is_none() { return false }
+
+	lines.all(|line| {
+		let mut values = line.split(',').map(|value| value.trim()).filter(|value| !value.is_empty()).peekable();
+		// with the shape id, a wall can leave out its sense
+		let counts = match values.peek() {
+			Some(&"0") => 5..=5,
+			Some(&"1") => 13..=13,
+			Some(&"2") => 7..=7,
+			Some(&"3") => 5..=6,
+			Some(&"4") => 8..=8,
+			_ => return false,
+		};
+
+		let mut count = 0;
+		let are_all_numbers = values.inspect(|_| count += 1).all(|value| value.parse::<f32>().is_ok());
+		// the header row can name attribute columns
+		let is_count_right = if has_header_row { count >= 2 } else { counts.contains(&count) };
+		are_all_numbers && is_count_right
+	})
+}
+
+// numbers separated by whitespace, ',' or ';', at least x, y, z and r, there can be a header row
+fn sniff_spheres(head: &[u8]) -> bool {
+	let is_sphere = |line: &str| {
+		let values = line.split(|ch: char| ch == ',' || ch == ';' || ch.is_whitespace()).filter(|value| !value.is_empty());
+		let mut count = 0;
+		let are_all_numbers = values.inspect(|_| count += 1).all(|value| value.parse::<f32>().is_ok());
+		are_all_numbers && count >= 4
+	};
+
+	let mut lines = head_lines(head).filter(|line| !line.starts_with('#')).peekable();
+	if lines.peek().is_some_and(|line| !is_sphere(line)) {
+		lines.next();
+	}
+
+	if lines.peek().is_none() { return false }
+
+	lines.all(is_sphere)
+}
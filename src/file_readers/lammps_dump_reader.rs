@@ -0,0 +1,202 @@
+use std::{fs, iter::{Enumerate, Peekable}, str::{FromStr, Lines}};
+
+use crate::maths::*;
+
+use super::{obj_reader::{split_with_columns, ReaderError}, yade_dem_reader::{Ball, Cuboid, YadeDemData}, yade_timeline::YadeFrame};
+
+
+// the default diameter of LAMMPS granular atoms is 1
+const DEFAULT_ATOM_RADIUS: Float = 0.5;
+
+// https://docs.lammps.org/dump.html, the text output of "dump custom" (LIGGGHTS' too), every timestep is a frame in file units,
+// atoms are balls, their columns other than id, position and radius become attributes and the simulation box is a cuboid
+pub fn read_frames_from_lammps_dump(path: &str) -> Result<Vec<YadeFrame>, ReaderError> {
+
+	let file_content = fs::read_to_string(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+	let mut lines = DumpLines { path, lines: file_content.lines().enumerate().peekable(), last_line_num: 0 };
+
+	let mut frames: Vec<YadeFrame> = vec![];
+	// of the last timestep
+	let mut atoms_count: Option<usize> = None;
+	let mut bounds = None;
+
+	while let Some((line_num, item)) = lines.next_item()? {
+
+		if item == "TIMESTEP" {
+			let (line_num, line) = lines.next_value_line("the timestep")?;
+			let iteration = lines.parse_token(line_num, line, 0, "timestep")?;
+			frames.push(YadeFrame { path: path.to_owned(), iteration: Some(iteration), data: YadeDemData::empty() });
+			atoms_count = None;
+			bounds = None;
+			continue;
+		}
+
+		let Some(frame) = frames.last_mut() else {
+			return Err(lines.error("the dump should start with 'ITEM: TIMESTEP', found".to_owned(), line_num, 1, item));
+		};
+
+		if item == "NUMBER OF ATOMS" {
+			let (line_num, line) = lines.next_value_line("the number of atoms")?;
+			atoms_count = Some(lines.parse_token(line_num, line, 0, "number of atoms")?);
+		} else if item.starts_with("BOX BOUNDS") {
+			// triclinic boxes ("BOX BOUNDS xy xz yz pp pp pp") list the bounding box of the cell and a tilt factor, it's drawn as the bounding box
+			let mut min = [0.0; 3];
+			let mut max = [0.0; 3];
+			for axis in 0..3 {
+				let (line_num, line) = lines.next_value_line("the box bounds")?;
+				min[axis] = lines.parse_token(line_num, line, 0, "box bound")?;
+				max[axis] = lines.parse_token(line_num, line, 1, "box bound")?;
+			}
+
+			let (min, max) = (Vec3::new(min[0], min[1], min[2]), Vec3::new(max[0], max[1], max[2]));
+			frame.data.cuboids.push(Cuboid { center: (min + max) / 2.0, extents: (max - min) / 2.0 });
+			bounds = Some((min, max));
+		} else if let Some(column_names) = item.strip_prefix("ATOMS") {
+			let Some(atoms_count) = atoms_count else {
+				return Err(lines.error("'ITEM: NUMBER OF ATOMS' should come before the atoms".to_owned(), line_num, 1, ""));
+			};
+
+			let columns = AtomColumns::from_names(column_names, bounds).map_err(|msg| lines.error(msg, line_num, 1, ""))?;
+			frame.data.attribute_names = columns.attributes.iter().map(|&column| columns.names[column].clone()).collect();
+
+			for _ in 0..atoms_count {
+				let (line_num, line) = lines.next_value_line("an atom")?;
+				let ball = lines.read_atom(line_num, line, &columns, frame.data.balls.len())?;
+				frame.data.balls.push(ball);
+			}
+		} else {
+			// UNITS, TIME and anything else that is not drawn
+			lines.skip_section();
+		}
+	}
+
+	if frames.is_empty() {
+		return Err(lines.error("the dump has no 'ITEM: TIMESTEP'".to_owned(), 1, 1, ""));
+	}
+
+	Ok(frames)
+}
+
+
+// where each value is in an atom line
+struct AtomColumns {
+	names: Vec<String>,
+	id: Option<usize>,
+	pos: [usize; 3],
+	// scaled positions (xs, ys, zs) are relative to the box, its min and size
+	unscale: Option<(Vec3, Vec3)>,
+	// the column and the factor to get a radius from it
+	rad: Option<(usize, Float)>,
+	attributes: Vec<usize>,
+}
+
+impl AtomColumns {
+	fn from_names(names: &str, bounds: Option<(Vec3, Vec3)>) -> Result<Self, String> {
+		let names: Vec<String> = names.split_whitespace().map(|name| name.to_owned()).collect();
+		let find = |name: &str| names.iter().position(|column_name| column_name == name);
+		let find_xyz = |suffix: &str| Some([find(&format!("x{}", suffix))?, find(&format!("y{}", suffix))?, find(&format!("z{}", suffix))?]);
+
+		let (pos, unscale) = if let Some(pos) = find_xyz("").or_else(|| find_xyz("u")) {
+			(pos, None)
+		} else if let Some(pos) = find_xyz("s").or_else(|| find_xyz("su")) {
+			let Some((min, max)) = bounds else {
+				return Err("scaled positions (xs, ys, zs) need the 'ITEM: BOX BOUNDS' before the atoms".to_owned());
+			};
+			(pos, Some((min, max - min)))
+		} else {
+			return Err("the atoms have no position columns, expected 'x y z', 'xu yu zu' or 'xs ys zs'".to_owned());
+		};
+
+		let rad = find("radius").map(|column| (column, 1.0)).or_else(|| find("diameter").map(|column| (column, 0.5)));
+		let id = find("id");
+
+		let is_geometry = |column: usize| Some(column) == id || pos.contains(&column) || rad.is_some_and(|(rad_column, _)| rad_column == column);
+		let attributes = (0..names.len()).filter(|&column| !is_geometry(column)).collect();
+
+		Ok(Self { names, id, pos, unscale, rad, attributes })
+	}
+}
+
+struct DumpLines<'a> {
+	path: &'a str,
+	lines: Peekable<Enumerate<Lines<'a>>>,
+	last_line_num: usize,
+}
+
+impl<'a> DumpLines<'a> {
+	fn error(&self, msg: String, line: usize, col: usize, token: &str) -> ReaderError {
+		ReaderError::BadToken { path: self.path.to_owned(), msg, line, col, token: token.to_owned() }
+	}
+
+	fn next_non_empty(&mut self) -> Option<(usize, &'a str)> {
+		let (line_index, line) = self.lines.by_ref().find(|(_, line)| !line.trim().is_empty())?;
+		self.last_line_num = line_index + 1;
+		Some((line_index + 1, line))
+	}
+
+	// the text after "ITEM:", None at the end of the file
+	fn next_item(&mut self) -> Result<Option<(usize, &'a str)>, ReaderError> {
+		let Some((line_num, line)) = self.next_non_empty() else { return Ok(None) };
+
+		match line.trim().strip_prefix("ITEM:") {
+			Some(item) => Ok(Some((line_num, item.trim()))),
+			None => Err(self.error("expected an 'ITEM:' line, found".to_owned(), line_num, 1, line.trim())),
+		}
+	}
+
+	fn next_value_line(&mut self, what: &str) -> Result<(usize, &'a str), ReaderError> {
+		match self.next_non_empty() {
+			Some((line_num, line)) if !line.trim().starts_with("ITEM:") => Ok((line_num, line)),
+			Some((line_num, line)) => Err(self.error(format!("missing {}, found", what), line_num, 1, line.trim())),
+			None => Err(self.error(format!("missing {} at the end of the file", what), self.last_line_num + 1, 1, "")),
+		}
+	}
+
+	fn skip_section(&mut self) {
+		while self.lines.next_if(|(_, line)| !line.trim().starts_with("ITEM:")).is_some() {}
+	}
+
+	fn parse_token<T: FromStr>(&self, line_num: usize, line: &str, index: usize, what: &str) -> Result<T, ReaderError> {
+		let Some((col, token)) = split_with_columns(line).nth(index) else {
+			return Err(self.error(format!("missing {}", what), line_num, line.trim_end().len() + 1, ""));
+		};
+
+		token.parse().map_err(|_| self.error(format!("could not parse the {}", what), line_num, col, token))
+	}
+
+	// 'index' is the id if there is no id column
+	fn read_atom(&self, line_num: usize, line: &str, columns: &AtomColumns, index: usize) -> Result<Ball, ReaderError> {
+		let tokens: Vec<(usize, &str)> = split_with_columns(line).collect();
+		if tokens.len() < columns.names.len() {
+			let msg = format!("expected {} values for an atom ({}), found {}", columns.names.len(), columns.names.join(" "), tokens.len());
+			return Err(self.error(msg, line_num, line.trim_end().len() + 1, ""));
+		}
+
+		let parse_float = |column: usize| {
+			let (col, token) = tokens[column];
+			token.parse::<Float>().map_err(|_| self.error(format!("could not parse the atom's {}", columns.names[column]), line_num, col, token))
+		};
+
+		let id = match columns.id {
+			Some(column) => {
+				let (col, token) = tokens[column];
+				token.parse().map_err(|_| self.error("could not parse the atom's id".to_owned(), line_num, col, token))?
+			}
+			None => index,
+		};
+
+		let mut pos = Vec3::new(parse_float(columns.pos[0])?, parse_float(columns.pos[1])?, parse_float(columns.pos[2])?);
+		if let Some((min, size)) = &columns.unscale {
+			pos = Vec3::new(min.x + pos.x * size.x, min.y + pos.y * size.y, min.z + pos.z * size.z);
+		}
+
+		let rad = match columns.rad {
+			Some((column, factor)) => parse_float(column)? * factor,
+			None => DEFAULT_ATOM_RADIUS,
+		};
+
+		let attributes = columns.attributes.iter().map(|&column| parse_float(column)).collect::<Result<_, _>>()?;
+
+		Ok(Ball { pos, rad, id, attributes })
+	}
+}
@@ -0,0 +1,63 @@
+use std::{io::{self, IsTerminal, Write}, sync::atomic::{AtomicBool, AtomicU64, Ordering}, thread, time::Duration};
+
+
+// smaller files load faster than the first report
+const MIN_REPORTED_FILE_LEN: u64 = 16 << 20;
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+// off once the terminal shows the scene, files reloaded then would write over it
+static ARE_REPORTS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+// bytes and lines parsed so far, shared by the threads reading a file
+pub struct LoadingProgress<'a> {
+	path: &'a str,
+	file_len: u64,
+	bytes: AtomicU64,
+	lines: AtomicU64,
+	is_done: AtomicBool,
+}
+
+impl<'a> LoadingProgress<'a> {
+	pub fn new(path: &'a str, file_len: u64) -> Self {
+		Self { path, file_len, bytes: AtomicU64::new(0), lines: AtomicU64::new(0), is_done: AtomicBool::new(false) }
+	}
+
+	// only big files are reported, and only if stderr is a terminal (not redirected to a file)
+	pub fn is_reported(&self) -> bool {
+		self.file_len >= MIN_REPORTED_FILE_LEN && io::stderr().is_terminal() && ARE_REPORTS_ENABLED.load(Ordering::Relaxed)
+	}
+
+	pub fn add(&self, bytes: usize, lines: usize) {
+		self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+		self.lines.fetch_add(lines as u64, Ordering::Relaxed);
+	}
+
+	// the thread running 'report_until_done' has to be unparked after this to stop right away
+	pub fn finish(&self) {
+		self.is_done.store(true, Ordering::Relaxed);
+	}
+
+	// rewrites a status line on stderr until 'finish' is called, then clears it
+	pub fn report_until_done(&self) {
+		let mut stderr = io::stderr();
+
+		while !self.is_done.load(Ordering::Relaxed) {
+			let bytes = self.bytes.load(Ordering::Relaxed);
+			let lines = self.lines.load(Ordering::Relaxed);
+			let percent = bytes as f64 / self.file_len as f64 * 100.0;
+			let to_mb = |bytes: u64| bytes as f64 / (1 << 20) as f64;
+
+			let _ = write!(stderr, "\r\x1b[2KLoading '{}': {:.0}% ({:.1} of {:.1} MB), {} lines", self.path, percent, to_mb(bytes), to_mb(self.file_len), lines);
+			let _ = stderr.flush();
+
+			thread::park_timeout(REPORT_INTERVAL);
+		}
+
+		let _ = write!(stderr, "\r\x1b[2K");
+		let _ = stderr.flush();
+	}
+}
+
+pub fn disable_progress_reports() {
+	ARE_REPORTS_ENABLED.store(false, Ordering::Relaxed);
+}
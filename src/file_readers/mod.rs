@@ -1,3 +1,18 @@
 
 pub mod obj_reader;
+pub mod mtl_reader;
 pub mod yade_dem_reader;
+pub mod loading_progress;
+pub mod yade_timeline;
+pub mod yade_interactions;
+pub mod yade_stream;
+pub mod lammps_dump_reader;
+pub mod sphere_list_reader;
+pub mod vtk_reader;
+pub mod binary_cache;
+pub mod coordinate_convention;
+pub mod stl_reader;
+pub mod ply_reader;
+pub mod off_reader;
+pub mod triangulation;
+pub mod file_format;
@@ -0,0 +1,52 @@
+use std::fs;
+
+use crate::{rendering::mesh::Material, Vec3};
+
+use super::obj_reader::{split_with_columns, ReaderError};
+
+
+// only 'newmtl' and the diffuse color 'Kd' are read, everything else is ignored
+pub fn read_materials_from_mtl_file(path: &str) -> Result<Vec<Material>, ReaderError> {
+
+	let file_content = fs::read_to_string(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+
+	let mut materials: Vec<Material> = vec![];
+
+	for (line_index, line) in file_content.lines().enumerate() {
+		let line_num = line_index + 1;
+
+		let line = line.split('#').next().unwrap_or_default();
+
+		let mut tokens = split_with_columns(line);
+		let Some((keyword_col, keyword)) = tokens.next() else { continue };
+
+		match keyword {
+			"newmtl" => {
+				let name = tokens.map(|(_, name)| name).collect::<Vec<_>>().join(" ");
+				materials.push(Material::with_name(name));
+			}
+
+			"Kd" => {
+				let Some(material) = materials.last_mut() else {
+					return Err(ReaderError::bad_format(format!("'Kd' before any 'newmtl': '{}'", line), line_num, keyword_col));
+				};
+
+				let mut next_channel = || -> Result<f32, ReaderError> {
+					let Some((col, channel_str)) = tokens.next() else {
+						return Err(ReaderError::bad_format(format!("not enough color channels: '{}'", line), line_num, line.trim_end().len() + 1));
+					};
+					channel_str.parse::<f32>().map_err(|_| ReaderError::bad_format(format!("cant parse float '{}': '{}'", channel_str, line), line_num, col))
+				};
+
+				let r = next_channel()?;
+				let g = next_channel()?;
+				let b = next_channel()?;
+				material.diffuse = Vec3::new(r, g, b);
+			}
+
+			_ => (),
+		}
+	}
+
+	Ok(materials)
+}
@@ -1,6 +1,8 @@
-use std::{num::{ParseFloatError, ParseIntError}, fmt::Display, fs, io::Error};
+use std::{fmt::Display, fs, io::Error, path::Path};
 
-use crate::{rendering::mesh::Mesh, Vec3};
+use crate::{rendering::mesh::{Material, Mesh, MeshPart, DEFAULT_CREASE_ANGLE_DEG}, Vec3};
+
+use super::{file_format::FileFormat, mtl_reader::read_materials_from_mtl_file, triangulation::triangulate_polygon};
 
 
 // TODO: make a macro to read an obj file in compile time and keeps it in the binary
@@ -17,25 +19,40 @@ macro_rules! xy_to_lin {
 #[derive(Debug)]
 pub enum ReaderError {
 	// BadFormat(&'a str),
-	BadFormat(String),
+	// line and column are 1-based
+	BadFormat { msg: String, line: usize, col: usize },
 	// FileNotFound(&'a str),
 	FileNotFound(String),
 	IOError(Error),
+	// path of a file whose content doesn't look like any supported format
+	UnknownFormat(String),
+	// a value that can't be read, 'token' is what was found instead (empty if nothing was)
+	BadToken { path: String, msg: String, line: usize, col: usize, token: String },
+	// every error in a file, when reading doesn't stop at the first one
+	Many(Vec<ReaderError>),
 }
 
 impl ReaderError {
-	fn from_io_error(err: Error, path: &str) -> ReaderError {
+	pub fn from_io_error(err: Error, path: &str) -> ReaderError {
 		match err.kind() {
 			std::io::ErrorKind::NotFound => ReaderError::FileNotFound(path.to_owned()),
 			_ => ReaderError::IOError(err),
 		}
 	}
 
+	pub fn bad_format(msg: String, line: usize, col: usize) -> ReaderError {
+		ReaderError::BadFormat { msg, line, col }
+	}
+
 	fn as_string(&self) -> String {
 		match self {
 			ReaderError::IOError(err) => format!("IO error {}", err),
-			ReaderError::BadFormat(st) => st.to_owned(),
+			ReaderError::BadFormat { msg, line, col } => format!("{}\nat line {}, column {}", msg, line, col),
 			ReaderError::FileNotFound(path) => format!("File '{}' Not found!", path),
+			ReaderError::BadToken { path, msg, line, col, token } if token.is_empty() => format!("{}:{}:{}: {}", path, line, col, msg),
+			ReaderError::BadToken { path, msg, line, col, token } => format!("{}:{}:{}: {} '{}'", path, line, col, msg, token),
+			ReaderError::Many(errors) => errors.iter().map(|err| err.as_string()).collect::<Vec<_>>().join("\n"),
+			ReaderError::UnknownFormat(path) => format!("Could not detect the format of '{}'\nsupported formats: {} (pick one with --format)", path, FileFormat::list_names()),
 		}
 	}
 }
@@ -49,20 +66,6 @@ impl Display for ReaderError {
 	}
 }
 
-impl From<ParseFloatError> for ReaderError {
-	fn from(err: ParseFloatError) -> Self {
-		Self::BadFormat(format!("Parse float error: '{}'", err))
-	}
-}
-
-impl From<ParseIntError> for ReaderError {
-	fn from(err: ParseIntError) -> Self {
-		Self::BadFormat(format!("Parse int error: '{}'", err))
-	}
-}
-
-// TODO: instead of propagating the shit all the way, I can make ReaderError have a string that expands with the error (shows lines that went bad)
-
 // TODO: make it Mesh::read_from_file
 pub fn read_mesh_from_obj_file(path: &str) -> Result<Mesh, ReaderError> {
 
@@ -72,83 +75,166 @@ pub fn read_mesh_from_obj_file(path: &str) -> Result<Mesh, ReaderError> {
 	let mut tris    = vec![];
 	let mut normals = vec![];
 	let mut normal_indices = vec![];
+	let mut tex_coords_count = 0;
+	let mut parts: Vec<MeshPart> = vec![];
+	let mut materials: Vec<Material> = vec![];
+	let mut mtl_paths = vec![];
+	let mut tri_materials = vec![];
+	let mut current_material = None;
+	let mut tri_smoothing_groups = vec![];
+	let mut current_smoothing_group = 0;
+	let mut has_smoothing_groups = false;
+
+	// normal indices have to match the triangle indices 1 by 1, if any face has no normals, they all get dropped
+	let mut has_face_without_normals = false;
+
+	for (line_index, line) in file_content.lines().enumerate() {
+		let line_num = line_index + 1;
+
+		// everything after a '#' is a comment
+		let line = line.split('#').next().unwrap_or_default();
+
+		let mut tokens = split_with_columns(line);
+		let Some((_, keyword)) = tokens.next() else { continue };
+
+		match keyword {
+			// 'v x y z [w]', the optional w (or vertex colors some exporters add) are ignored
+			"v" => {
+				verts.push(next_float(&mut tokens, line, line_num)?);
+				verts.push(next_float(&mut tokens, line, line_num)?);
+				verts.push(next_float(&mut tokens, line, line_num)?);
+			}
 
-	let mut has_found_normals = false;
-
-	for (i, line) in file_content.lines().enumerate() {
-		let line_split_by_space = line.split(' ').skip(1);
+			"vn" => {
+				normals.push(next_float(&mut tokens, line, line_num)?);
+				normals.push(next_float(&mut tokens, line, line_num)?);
+				normals.push(next_float(&mut tokens, line, line_num)?);
+			}
 
-		if line.starts_with("v ") {
-			for vert_str in line_split_by_space {
-				if vert_str.is_empty() { continue }
+			// 'o name' and 'g name [names...]' start a new part, faces before any of them go into a "default" one
+			"o" | "g" => {
+				let name = tokens.map(|(_, name)| name).collect::<Vec<_>>().join(" ");
+				let name = if name.is_empty() { "default".to_owned() } else { name };
 
-				let vert = vert_str.parse::<f32>().or(
-					Err(
-						ReaderError::BadFormat(format!("cant parse float '{}'\nline {}: '{}'", vert_str, i+1, line))
-					)
-				)?;
-				verts.push(vert);
+				let tris_count = tris.len() / 3;
+				match parts.last_mut() {
+					Some(last) if last.tris.is_empty() => last.name = name,
+					_ => parts.push(MeshPart { name, tris: tris_count..tris_count }),
+				}
 			}
 
-			continue;
-		}
+			// material libraries are looked for next to the OBJ, missing ones are skipped
+			"mtllib" => {
+				let obj_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+				for (_, mtl_name) in tokens {
+					let mtl_path = obj_dir.join(mtl_name).to_string_lossy().into_owned();
+					match read_materials_from_mtl_file(&mtl_path) {
+						Ok(read_materials) => materials.extend(read_materials),
+						Err(ReaderError::FileNotFound(_)) => (),
+						Err(err) => return Err(err),
+					}
+					mtl_paths.push(mtl_path);
+				}
+			}
 
-		if line.starts_with("vn ") {
-			for normal_str in line_split_by_space {
-				if normal_str.is_empty() { continue; }
+			// materials not found in any library get the default color
+			"usemtl" => {
+				let name = tokens.map(|(_, name)| name).collect::<Vec<_>>().join(" ");
+				let material_index = match materials.iter().position(|material| material.name == name) {
+					Some(index) => index,
+					None => {
+						materials.push(Material::with_name(name));
+						materials.len() - 1
+					}
+				};
+				current_material = Some(material_index as u32);
+			}
 
-				let normal = normal_str.parse::<f32>().or(
-					Err(
-						ReaderError::BadFormat(format!("cant parse float '{}'\nline {}: '{}'", normal_str, i+1, line))
-					)
-				)?;
-				normals.push(normal);
+			// 's off' and 's 0' turn smoothing off
+			"s" => {
+				let Some((col, group_str)) = tokens.next() else {
+					return Err(ReaderError::bad_format(format!("missing smoothing group: '{}'", line), line_num, line.trim_end().len() + 1));
+				};
+
+				current_smoothing_group = match group_str {
+					"off" => 0,
+					_ => group_str.parse::<u32>().map_err(|_| ReaderError::bad_format(format!("cant parse smoothing group '{}'", group_str), line_num, col))?,
+				};
+				has_smoothing_groups = true;
 			}
 
-			has_found_normals = true;
-			continue;
-		}
+			// texture coordinates are not used, but need to be counted to validate face indices
+			"vt" => tex_coords_count += 1,
+
+			// quads and n-gons are triangulated, fan for convex faces, ear clipping for concave ones
+			"f" => {
+				let counts = IndexCounts {
+					verts: verts.len() / 3,
+					tex_coords: tex_coords_count,
+					normals: normals.len() / 3,
+				};
+
+				let mut face_verts   = vec![];
+				let mut face_normals = vec![];
+
+				for (col, corner) in tokens {
+					let (vertex_index, normal_index) = parse_face_corner(corner, col, line_num, &counts)?;
+					face_verts.push(vertex_index);
+
+					if let Some(normal_index) = normal_index {
+						face_normals.push(normal_index);
+					}
+				}
+
+				if face_verts.len() < 3 {
+					return Err(ReaderError::bad_format(format!("face needs at least 3 vertices, got {}: '{}'", face_verts.len(), line), line_num, 1));
+				}
+
+				let has_normals = !face_normals.is_empty();
+				if has_normals && face_normals.len() != face_verts.len() {
+					return Err(ReaderError::bad_format(format!("face mixes corners with and without normals: '{}'", line), line_num, 1));
+				}
+				has_face_without_normals |= !has_normals;
+
+				let corners: Vec<Vec3> = face_verts.iter().map(|&vertex_index| {
+					let it = vertex_index as usize * 3;
+					Vec3::new(verts[it], verts[it + 1], verts[it + 2])
+				}).collect();
+
+				for corner_index in triangulate_polygon(&corners) {
+					tris.push(face_verts[corner_index]);
+					if has_normals {
+						normal_indices.push(face_normals[corner_index]);
+					}
+				}
 
-		// TODO: handle Ngons
-		// https://stackoverflow.com/questions/60660726/how-can-i-load-and-render-an-obj-file-that-may-include-triangles-quads-or-n-go
-		if line.starts_with("f ") {
-			for indices_group in line_split_by_space {
-				// println!("{}", value);
-
-				// f v1/vt1 v2/vt2 v3/vt3
-				// face references (vertex_index/texture_index/normal_index)
-				let mut indices = indices_group.split('/');
-
-				let vertex_index_str = indices.next().ok_or(ReaderError::BadFormat(
-					format!("cant skip indices iterator\nline {}: '{}'", i, line)
-				))?;
-				let vertex_index = vertex_index_str.parse::<u16>().or(
-					Err(
-						ReaderError::BadFormat(format!("cant parse int 16 '{}'\nline {}: '{}'", vertex_index_str, i+1, line))
-					)
-				)? - 1;
-				tris.push(vertex_index);
-
-				// skip texture coordinates
-				indices.next();
-
-				if has_found_normals {
-					let normal_index = indices.next().ok_or(ReaderError::BadFormat(
-						format!("cant parse normal iterator\nline {}: '{}'", i+1, line)
-					))?;
-					let normal_index = normal_index.parse::<u16>()? - 1;
-					normal_indices.push(normal_index);		
+				tri_materials.resize(tris.len() / 3, current_material);
+				tri_smoothing_groups.resize(tris.len() / 3, current_smoothing_group);
+
+				if parts.is_empty() {
+					parts.push(MeshPart { name: "default".to_owned(), tris: 0..0 });
 				}
+				parts.last_mut().unwrap().tris.end = tris.len() / 3;
 			}
 
-			continue;
+			_ => (),
 		}
+	}
 
-		if !has_found_normals {
-			// TODO: calculate normals manually
-		}
+	if has_face_without_normals {
+		normal_indices.clear();
 	}
 
+	if materials.is_empty() {
+		tri_materials.clear();
+	}
+
+	if !has_smoothing_groups {
+		tri_smoothing_groups.clear();
+	}
+
+	// objects or groups that never got faces
+	parts.retain(|part| !part.tris.is_empty());
 
 	// BUNNY config
 	// mesh.invert_mesh_yz();
@@ -179,12 +265,23 @@ pub fn read_mesh_from_obj_file(path: &str) -> Result<Mesh, ReaderError> {
 	// };
 	// #endif
 
-	Ok(Mesh {
+	let mut mesh = Mesh {
 		verts,
 		tris_indices: tris,
 		normals,
 		normal_indices,
-	})
+		parts,
+		materials,
+		tri_materials,
+		face_normals: vec![],
+		tri_smoothing_groups,
+		vert_colors: vec![],
+		mtl_paths,
+	};
+
+	mesh.compute_missing_normals(DEFAULT_CREASE_ANGLE_DEG);
+
+	Ok(mesh)
 }
 
 // TODO: put it in Mesh struct
@@ -206,3 +303,68 @@ pub fn translate_mesh(mesh_mut: &mut Mesh, translation: &Vec3) {
 		i += 1;
 	}
 }
+
+
+struct IndexCounts {
+	verts: usize,
+	tex_coords: usize,
+	normals: usize,
+}
+
+// splits by any whitespace (tabs, repeated spaces) and yields each token with its 1-based column
+pub fn split_with_columns(line: &str) -> impl Iterator<Item = (usize, &str)> {
+	line.split(char::is_whitespace)
+		.filter(|token| !token.is_empty())
+		.map(move |token| (token.as_ptr() as usize - line.as_ptr() as usize + 1, token))
+}
+
+fn next_float<'a>(tokens: &mut impl Iterator<Item = (usize, &'a str)>, line: &str, line_num: usize) -> Result<f32, ReaderError> {
+	let Some((col, float_str)) = tokens.next() else {
+		return Err(ReaderError::bad_format(format!("not enough coordinates: '{}'", line), line_num, line.trim_end().len() + 1));
+	};
+
+	float_str.parse::<f32>().map_err(|_| ReaderError::bad_format(format!("cant parse float '{}': '{}'", float_str, line), line_num, col))
+}
+
+// accepts 'v', 'v/vt', 'v//vn' and 'v/vt/vn', returns the zero-based vertex and normal indices
+fn parse_face_corner(corner: &str, col: usize, line_num: usize, counts: &IndexCounts) -> Result<(u32, Option<u32>), ReaderError> {
+	let mut parts = corner.split('/');
+	let mut part_col = col;
+
+	let vertex_str = parts.next().unwrap_or_default();
+	let vertex_index = resolve_index(vertex_str, counts.verts, "vertex", part_col, line_num)?;
+	part_col += vertex_str.len() + 1;
+
+	if let Some(tex_coord_str) = parts.next() {
+		if !tex_coord_str.is_empty() {
+			resolve_index(tex_coord_str, counts.tex_coords, "texture coordinate", part_col, line_num)?;
+		}
+		part_col += tex_coord_str.len() + 1;
+	}
+
+	let normal_index = match parts.next() {
+		Some(normal_str) if !normal_str.is_empty() => Some(resolve_index(normal_str, counts.normals, "normal", part_col, line_num)?),
+		_ => None,
+	};
+
+	if parts.next().is_some() {
+		return Err(ReaderError::bad_format(format!("too many '/' in face corner '{}'", corner), line_num, col));
+	}
+
+	Ok((vertex_index, normal_index))
+}
+
+// OBJ indices start at 1, negative ones are relative to the end of what was read so far
+fn resolve_index(index_str: &str, count: usize, name: &str, col: usize, line_num: usize) -> Result<u32, ReaderError> {
+	let index = index_str.parse::<i64>().map_err(|_|
+		ReaderError::bad_format(format!("cant parse {} index '{}'", name, index_str), line_num, col)
+	)?;
+
+	let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+
+	if index == 0 || resolved < 0 || resolved >= count as i64 {
+		return Err(ReaderError::bad_format(format!("{} index {} out of range, {} read so far", name, index, count), line_num, col));
+	}
+
+	Ok(resolved as u32)
+}
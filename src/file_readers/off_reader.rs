@@ -0,0 +1,153 @@
+use std::fs;
+
+use crate::rendering::mesh::{Mesh, DEFAULT_CREASE_ANGLE_DEG};
+
+use super::{obj_reader::{split_with_columns, ReaderError}, triangulation::push_triangulated_face};
+
+
+// https://segeval.cs.princeton.edu/public/off_format.html
+// supports OFF, COFF (vertex colors), NOFF (vertex normals) and CNOFF, per face colors are ignored
+pub fn read_mesh_from_off_file(path: &str) -> Result<Mesh, ReaderError> {
+
+	let file_content = fs::read_to_string(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+
+	// non empty lines, without '#' comments
+	let mut lines = file_content.lines().enumerate()
+		.map(|(line_index, line)| (line_index + 1, line.split('#').next().unwrap_or("")))
+		.filter(|(_, line)| !line.trim().is_empty());
+
+	let Some((line_num, line)) = lines.next() else {
+		return Err(ReaderError::bad_format("empty OFF file".to_owned(), 1, 1));
+	};
+
+	let mut tokens = split_with_columns(line);
+	let (col, keyword) = tokens.next().unwrap();
+
+	let (has_colors, has_normals) = match keyword {
+		"OFF"   => (false, false),
+		"COFF"  => (true,  false),
+		"NOFF"  => (false, true),
+		"CNOFF" | "NCOFF" => (true, true),
+		_ => return Err(ReaderError::bad_format(format!("not an OFF file, it should start with 'OFF', 'COFF', 'NOFF' or 'CNOFF', not '{}'", keyword), line_num, col)),
+	};
+
+	// the counts can be in the same line as the keyword
+	let mut counts_line = (line_num, line);
+	let mut counts_tokens: Vec<(usize, &str)> = tokens.collect();
+
+	if let Some(&(col, "BINARY")) = counts_tokens.first() {
+		return Err(ReaderError::bad_format("binary OFF files are not supported".to_owned(), line_num, col));
+	}
+
+	if counts_tokens.is_empty() {
+		let Some((line_num, line)) = lines.next() else {
+			return Err(ReaderError::bad_format("OFF file without vertex and face counts".to_owned(), line_num + 1, 1));
+		};
+		counts_line = (line_num, line);
+		counts_tokens = split_with_columns(line).collect();
+	}
+
+	let (line_num, line) = counts_line;
+	let parse_count = |i: usize, what: &str| {
+		let Some(&(col, count_str)) = counts_tokens.get(i) else {
+			return Err(ReaderError::bad_format(format!("missing {}: '{}'", what, line), line_num, line.trim_end().len() + 1));
+		};
+		count_str.parse::<usize>().map_err(|_| ReaderError::bad_format(format!("cant parse {} '{}'", what, count_str), line_num, col))
+	};
+
+	let verts_count = parse_count(0, "vertex count")?;
+	let faces_count = parse_count(1, "face count")?;
+
+	// not allocated from the header count, a broken one could ask for more memory than there is
+	let mut verts = vec![];
+	let mut normals = vec![];
+	let mut vert_colors = vec![];
+	// ints from 0 to 255 if any channel is above 1 or none of them is written as a float, else floats from 0 to 1
+	let mut has_int_color_channel = false;
+	let mut has_float_color_token = false;
+
+	for vert_i in 0..verts_count {
+		let Some((line_num, line)) = lines.next() else {
+			return Err(ReaderError::bad_format(format!("expected {} vertices, found {}", verts_count, vert_i), file_content.lines().count(), 1));
+		};
+
+		let mut tokens = split_with_columns(line);
+		// and its token
+		let mut next_float = |what: &str| {
+			let Some((col, float_str)) = tokens.next() else {
+				return Err(ReaderError::bad_format(format!("missing {}: '{}'", what, line), line_num, line.trim_end().len() + 1));
+			};
+			let float = float_str.parse::<f32>().map_err(|_| ReaderError::bad_format(format!("cant parse {} '{}'", what, float_str), line_num, col))?;
+			Ok((float, float_str))
+		};
+
+		for _ in 0..3 { verts.push(next_float("vertex coordinate")?.0); }
+
+		if has_normals {
+			for _ in 0..3 { normals.push(next_float("vertex normal")?.0); }
+		}
+
+		// colors are either ints from 0 to 255 or floats from 0 to 1, decided for the whole file, alpha is ignored
+		if has_colors {
+			for _ in 0..3 {
+				let (channel, channel_str) = next_float("vertex color")?;
+				has_int_color_channel |= channel > 1.0;
+				has_float_color_token |= channel_str.contains(['.', 'e', 'E']);
+				vert_colors.push(channel);
+			}
+		}
+	}
+
+	if has_int_color_channel || !has_float_color_token {
+		vert_colors.iter_mut().for_each(|channel| *channel /= 255.0);
+	}
+
+	let mut tris_indices = vec![];
+	let mut face = vec![];
+
+	for face_i in 0..faces_count {
+		let Some((line_num, line)) = lines.next() else {
+			return Err(ReaderError::bad_format(format!("expected {} faces, found {}", faces_count, face_i), file_content.lines().count(), 1));
+		};
+
+		let mut tokens = split_with_columns(line);
+
+		let (col, count_str) = tokens.next().unwrap();
+		let count = count_str.parse::<usize>().map_err(|_|
+			ReaderError::bad_format(format!("cant parse face vertex count '{}'", count_str), line_num, col)
+		)?;
+
+		face.clear();
+		for _ in 0..count {
+			let Some((col, index_str)) = tokens.next() else {
+				return Err(ReaderError::bad_format(format!("face has less than {} vertices: '{}'", count, line), line_num, line.trim_end().len() + 1));
+			};
+
+			let vert_index = index_str.parse::<usize>().map_err(|_|
+				ReaderError::bad_format(format!("cant parse vertex index '{}'", index_str), line_num, col)
+			)?;
+
+			if vert_index >= verts_count {
+				return Err(ReaderError::bad_format(format!("vertex index {} out of range, there are {} vertices", vert_index, verts_count), line_num, col));
+			}
+
+			face.push(vert_index as u32);
+		}
+
+		if count >= 3 {
+			push_triangulated_face(&verts, &face, &mut tris_indices);
+		}
+	}
+
+	let mut mesh = Mesh::from_verts_and_indices(verts, tris_indices);
+
+	if has_normals {
+		mesh.normal_indices = mesh.tris_indices.clone();
+		mesh.normals = normals;
+	}
+	mesh.vert_colors = vert_colors;
+
+	mesh.compute_missing_normals(DEFAULT_CREASE_ANGLE_DEG);
+
+	Ok(mesh)
+}
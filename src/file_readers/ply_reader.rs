@@ -0,0 +1,442 @@
+use std::fs;
+
+use crate::rendering::mesh::{Mesh, DEFAULT_CREASE_ANGLE_DEG};
+
+use super::{obj_reader::{split_with_columns, ReaderError}, triangulation::push_triangulated_face};
+
+
+// http://paulbourke.net/dataformats/ply/
+// reads the 'vertex' element (position, normals and colors) and the 'face' element (polygons), other elements are skipped
+pub fn read_mesh_from_ply_file(path: &str) -> Result<Mesh, ReaderError> {
+
+	let file_content = fs::read(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+
+	let header = read_header(&file_content)?;
+	let mut body = PlyBodyReader::new(&header, &file_content)?;
+
+	let mut verts = vec![];
+	let mut normals = vec![];
+	let mut vert_colors = vec![];
+	let mut tris_indices = vec![];
+
+	for element in header.elements.iter() {
+		match element.name.as_str() {
+			"vertex" => read_vertices(element, &mut body, &mut verts, &mut normals, &mut vert_colors)?,
+			"face"   => read_faces(element, &mut body, &verts, &mut tris_indices)?,
+			_ => {
+				for _ in 0..element.count {
+					body.begin_element()?;
+					for property in element.properties.iter() {
+						body.skip_property(property)?;
+					}
+				}
+			}
+		}
+	}
+
+	let mut mesh = Mesh::from_verts_and_indices(verts, tris_indices);
+
+	if !normals.is_empty() {
+		mesh.normal_indices = mesh.tris_indices.clone();
+		mesh.normals = normals;
+	}
+	mesh.vert_colors = vert_colors;
+
+	mesh.compute_missing_normals(DEFAULT_CREASE_ANGLE_DEG);
+
+	Ok(mesh)
+}
+
+fn read_vertices(element: &PlyElement, body: &mut PlyBodyReader, verts: &mut Vec<f32>, normals: &mut Vec<f32>, vert_colors: &mut Vec<f32>) -> Result<(), ReaderError> {
+
+	let find = |name: &str| element.properties.iter().position(|property| property.name == name);
+
+	// positions, normals and colors are single values, a list can't be one
+	let scalar_type = |i: usize| match element.properties[i].kind {
+		PlyPropertyKind::Scalar(ty) => Ok(ty),
+		PlyPropertyKind::List(..) => {
+			let property = &element.properties[i];
+			Err(ReaderError::bad_format(format!("the vertex property '{}' should be a single value, not a list", property.name), property.line_num, property.type_col))
+		}
+	};
+
+	let (Some(x_i), Some(y_i), Some(z_i)) = (find("x"), find("y"), find("z")) else {
+		return Err(ReaderError::bad_format("'vertex' element without 'x', 'y' and 'z' properties".to_owned(), element.line_num, 1));
+	};
+	for i in [x_i, y_i, z_i] {
+		scalar_type(i)?;
+	}
+
+	let normal_is = match (find("nx"), find("ny"), find("nz")) {
+		(Some(nx_i), Some(ny_i), Some(nz_i)) => {
+			for i in [nx_i, ny_i, nz_i] {
+				scalar_type(i)?;
+			}
+			Some([nx_i, ny_i, nz_i])
+		}
+		_ => None,
+	};
+
+	// with the max value of their type
+	let colors = match (find("red").or(find("r")), find("green").or(find("g")), find("blue").or(find("b"))) {
+		(Some(r_i), Some(g_i), Some(b_i)) => Some([(r_i, scalar_type(r_i)?.color_max()), (g_i, scalar_type(g_i)?.color_max()), (b_i, scalar_type(b_i)?.color_max())]),
+		_ => None,
+	};
+
+	let mut values = vec![0.0; element.properties.len()];
+	for _ in 0..element.count {
+		body.begin_element()?;
+
+		for (value, property) in values.iter_mut().zip(element.properties.iter()) {
+			*value = match property.kind {
+				PlyPropertyKind::Scalar(ty) => body.next_value(ty)?,
+				PlyPropertyKind::List(..) => { body.skip_property(property)?; 0.0 },
+			};
+		}
+
+		verts.push(values[x_i] as f32);
+		verts.push(values[y_i] as f32);
+		verts.push(values[z_i] as f32);
+
+		if let Some(normal_is) = normal_is {
+			normals.extend(normal_is.map(|i| values[i] as f32));
+		}
+
+		// integer colors go from 0 to their max value (255 for uchar), float colors from 0 to 1
+		if let Some(colors) = colors {
+			vert_colors.extend(colors.map(|(i, color_max)| (values[i] / color_max) as f32));
+		}
+	}
+
+	Ok(())
+}
+
+fn read_faces(element: &PlyElement, body: &mut PlyBodyReader, verts: &[f32], tris_indices: &mut Vec<u32>) -> Result<(), ReaderError> {
+
+	let indices_i = element.properties.iter().position(|property|
+		matches!(property.kind, PlyPropertyKind::List(..)) && (property.name == "vertex_indices" || property.name == "vertex_index")
+	);
+
+	let Some(indices_i) = indices_i else {
+		return Err(ReaderError::bad_format("'face' element without a 'vertex_indices' list property".to_owned(), element.line_num, 1));
+	};
+
+	let verts_count = verts.len() / 3;
+	let mut face = vec![];
+
+	for face_i in 0..element.count {
+		body.begin_element()?;
+
+		for (property_i, property) in element.properties.iter().enumerate() {
+			if property_i != indices_i {
+				body.skip_property(property)?;
+				continue;
+			}
+
+			let PlyPropertyKind::List(count_ty, item_ty) = property.kind else { unreachable!() };
+
+			face.clear();
+			let count = body.next_value(count_ty)? as usize;
+			for _ in 0..count {
+				let vert_index = body.next_value(item_ty)?;
+				if vert_index < 0.0 || vert_index as usize >= verts_count {
+					return Err(body.error(format!("face {} has vertex index {} out of range, there are {} vertices", face_i, vert_index, verts_count)));
+				}
+				face.push(vert_index as u32);
+			}
+
+			if count >= 3 {
+				push_triangulated_face(verts, &face, tris_indices);
+			}
+		}
+	}
+
+	Ok(())
+}
+
+
+#[derive(Clone, Copy)]
+enum PlyFormat {
+	Ascii,
+	BinaryLittleEndian,
+	BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum PlyType {
+	I8, U8, I16, U16, I32, U32, F32, F64,
+}
+
+impl PlyType {
+	fn from_name(name: &str) -> Option<PlyType> {
+		match name {
+			"char"   | "int8"    => Some(PlyType::I8),
+			"uchar"  | "uint8"   => Some(PlyType::U8),
+			"short"  | "int16"   => Some(PlyType::I16),
+			"ushort" | "uint16"  => Some(PlyType::U16),
+			"int"    | "int32"   => Some(PlyType::I32),
+			"uint"   | "uint32"  => Some(PlyType::U32),
+			"float"  | "float32" => Some(PlyType::F32),
+			"double" | "float64" => Some(PlyType::F64),
+			_ => None,
+		}
+	}
+
+	fn size(&self) -> usize {
+		match self {
+			PlyType::I8  | PlyType::U8  => 1,
+			PlyType::I16 | PlyType::U16 => 2,
+			PlyType::I32 | PlyType::U32 | PlyType::F32 => 4,
+			PlyType::F64 => 8,
+		}
+	}
+
+	fn color_max(&self) -> f64 {
+		match self {
+			PlyType::I8  => i8::MAX  as f64,
+			PlyType::U8  => u8::MAX  as f64,
+			PlyType::I16 => i16::MAX as f64,
+			PlyType::U16 => u16::MAX as f64,
+			PlyType::I32 => i32::MAX as f64,
+			PlyType::U32 => u32::MAX as f64,
+			PlyType::F32 | PlyType::F64 => 1.0,
+		}
+	}
+}
+
+enum PlyPropertyKind {
+	Scalar(PlyType),
+	// type of the count, type of each item
+	List(PlyType, PlyType),
+}
+
+struct PlyProperty {
+	name: String,
+	kind: PlyPropertyKind,
+	// of its header line, for errors
+	line_num: usize,
+	type_col: usize,
+}
+
+struct PlyElement {
+	name: String,
+	count: usize,
+	properties: Vec<PlyProperty>,
+	line_num: usize,
+}
+
+struct PlyHeader {
+	format: PlyFormat,
+	elements: Vec<PlyElement>,
+	// where the data starts
+	body_start: usize,
+	body_line_num: usize,
+}
+
+fn read_header(file_content: &[u8]) -> Result<PlyHeader, ReaderError> {
+
+	const END_HEADER: &[u8] = b"end_header";
+
+	let Some(end_header_at) = file_content.windows(END_HEADER.len()).position(|window| window == END_HEADER) else {
+		return Err(ReaderError::bad_format("PLY header has no 'end_header'".to_owned(), 1, 1));
+	};
+
+	let body_start = match file_content[end_header_at..].iter().position(|&byte| byte == b'\n') {
+		Some(line_end) => end_header_at + line_end + 1,
+		None => file_content.len(),
+	};
+
+	let header_str = std::str::from_utf8(&file_content[..end_header_at]).map_err(|_|
+		ReaderError::bad_format("PLY header is not text".to_owned(), 1, 1)
+	)?;
+
+	let mut format = None;
+	let mut elements: Vec<PlyElement> = vec![];
+	let mut lines_count = 0;
+
+	for (line_index, line) in header_str.lines().enumerate() {
+		let line_num = line_index + 1;
+		lines_count = line_num;
+
+		let mut tokens = split_with_columns(line);
+		let Some((keyword_col, keyword)) = tokens.next() else { continue };
+
+		if line_num == 1 {
+			if keyword != "ply" {
+				return Err(ReaderError::bad_format(format!("not a PLY file, it should start with 'ply', not '{}'", keyword), line_num, keyword_col));
+			}
+			continue;
+		}
+
+		let mut next_token = |what: &str| tokens.next().ok_or_else(||
+			ReaderError::bad_format(format!("missing {}: '{}'", what, line), line_num, line.trim_end().len() + 1)
+		);
+
+		match keyword {
+			"format" => {
+				let (col, format_str) = next_token("format")?;
+				format = Some(match format_str {
+					"ascii" => PlyFormat::Ascii,
+					"binary_little_endian" => PlyFormat::BinaryLittleEndian,
+					"binary_big_endian" => PlyFormat::BinaryBigEndian,
+					_ => return Err(ReaderError::bad_format(format!("unknown PLY format '{}'", format_str), line_num, col)),
+				});
+			}
+
+			"element" => {
+				let (_, name) = next_token("element name")?;
+				let (col, count_str) = next_token("element count")?;
+				let count = count_str.parse::<usize>().map_err(|_|
+					ReaderError::bad_format(format!("cant parse element count '{}'", count_str), line_num, col)
+				)?;
+
+				elements.push(PlyElement { name: name.to_owned(), count, properties: vec![], line_num });
+			}
+
+			"property" => {
+				let parse_type = |(col, type_str): (usize, &str)| PlyType::from_name(type_str).ok_or_else(||
+					ReaderError::bad_format(format!("unknown property type '{}'", type_str), line_num, col)
+				);
+
+				let type_token = next_token("property type")?;
+				let type_col = type_token.0;
+				let kind = if type_token.1 == "list" {
+					let count_ty = parse_type(next_token("list count type")?)?;
+					let item_ty  = parse_type(next_token("list item type")?)?;
+					PlyPropertyKind::List(count_ty, item_ty)
+				} else {
+					PlyPropertyKind::Scalar(parse_type(type_token)?)
+				};
+
+				let (_, name) = next_token("property name")?;
+
+				let Some(element) = elements.last_mut() else {
+					return Err(ReaderError::bad_format("property before any element".to_owned(), line_num, keyword_col));
+				};
+				element.properties.push(PlyProperty { name: name.to_owned(), kind, line_num, type_col });
+			}
+
+			"comment" | "obj_info" => (),
+
+			_ => return Err(ReaderError::bad_format(format!("unknown PLY header keyword '{}'", keyword), line_num, keyword_col)),
+		}
+	}
+
+	let Some(format) = format else {
+		return Err(ReaderError::bad_format("PLY header has no 'format'".to_owned(), 2, 1));
+	};
+
+	Ok(PlyHeader { format, elements, body_start, body_line_num: lines_count + 2 })
+}
+
+
+// reads values one by one, in ASCII each element is a line
+struct PlyBodyReader<'a> {
+	format: PlyFormat,
+	bytes: &'a [u8],
+	pos: usize,
+
+	// ASCII only
+	lines: std::iter::Enumerate<std::str::Lines<'a>>,
+	line_tokens: Vec<(usize, &'a str)>,
+	token_i: usize,
+	line_num: usize,
+	body_line_num: usize,
+}
+
+impl<'a> PlyBodyReader<'a> {
+	fn new(header: &PlyHeader, file_content: &'a [u8]) -> Result<Self, ReaderError> {
+		let bytes = &file_content[header.body_start..];
+
+		let body_str = match header.format {
+			PlyFormat::Ascii => std::str::from_utf8(bytes).map_err(|_|
+				ReaderError::bad_format("ASCII PLY data is not text".to_owned(), header.body_line_num, 1)
+			)?,
+			_ => "",
+		};
+
+		Ok(Self {
+			format: header.format,
+			bytes,
+			pos: 0,
+			lines: body_str.lines().enumerate(),
+			line_tokens: vec![],
+			token_i: 0,
+			line_num: header.body_line_num,
+			body_line_num: header.body_line_num,
+		})
+	}
+
+	fn error(&self, msg: String) -> ReaderError {
+		match self.format {
+			PlyFormat::Ascii => ReaderError::bad_format(msg, self.line_num, self.line_tokens.get(self.token_i.saturating_sub(1)).map_or(1, |token| token.0)),
+			_ => ReaderError::bad_format(format!("{} (at byte {} of the binary data)", msg, self.pos), self.body_line_num, 1),
+		}
+	}
+
+	fn begin_element(&mut self) -> Result<(), ReaderError> {
+		let PlyFormat::Ascii = self.format else { return Ok(()) };
+
+		for (line_index, line) in self.lines.by_ref() {
+			self.line_num = self.body_line_num + line_index;
+			self.line_tokens = split_with_columns(line).collect();
+			self.token_i = 0;
+
+			if !self.line_tokens.is_empty() { return Ok(()) }
+		}
+
+		Err(self.error("unexpected end of file, there are less elements than the header says".to_owned()))
+	}
+
+	fn next_value(&mut self, ty: PlyType) -> Result<f64, ReaderError> {
+		match self.format {
+			PlyFormat::Ascii => {
+				let Some(&(_, token)) = self.line_tokens.get(self.token_i) else {
+					return Err(self.error("not enough values in this line".to_owned()));
+				};
+				self.token_i += 1;
+
+				token.parse::<f64>().map_err(|_| self.error(format!("cant parse number '{}'", token)))
+			}
+
+			PlyFormat::BinaryLittleEndian | PlyFormat::BinaryBigEndian => {
+				let size = ty.size();
+				let Some(value_bytes) = self.bytes.get(self.pos .. self.pos + size) else {
+					return Err(self.error("unexpected end of file".to_owned()));
+				};
+
+				let mut raw = [0u8; 8];
+				raw[..size].copy_from_slice(value_bytes);
+				if let PlyFormat::BinaryBigEndian = self.format {
+					raw[..size].reverse();
+				}
+				self.pos += size;
+
+				Ok(match ty {
+					PlyType::I8  => i8::from_le_bytes([raw[0]]) as f64,
+					PlyType::U8  => raw[0] as f64,
+					PlyType::I16 => i16::from_le_bytes([raw[0], raw[1]]) as f64,
+					PlyType::U16 => u16::from_le_bytes([raw[0], raw[1]]) as f64,
+					PlyType::I32 => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+					PlyType::U32 => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+					PlyType::F32 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+					PlyType::F64 => f64::from_le_bytes(raw),
+				})
+			}
+		}
+	}
+
+	fn skip_property(&mut self, property: &PlyProperty) -> Result<(), ReaderError> {
+		match property.kind {
+			PlyPropertyKind::Scalar(ty) => { self.next_value(ty)?; }
+			PlyPropertyKind::List(count_ty, item_ty) => {
+				let count = self.next_value(count_ty)? as usize;
+				for _ in 0..count {
+					self.next_value(item_ty)?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,165 @@
+use std::fs;
+
+use crate::maths::*;
+
+use super::{obj_reader::ReaderError, yade_dem_reader::{Ball, YadeDemData}};
+
+
+// how to read a sphere list, set from the command line
+#[derive(Clone, Default, Debug)]
+pub struct SphereListOptions {
+	// None guesses it from the first sphere: ',' or ';' if it has them, else any whitespace
+	pub delimiter: Option<char>,
+	// what each column is, like a header row, overrides the file's header
+	pub column_names: Option<Vec<String>>,
+	// lines at the start of the file that are not read at all
+	pub skip_lines: usize,
+}
+
+impl SphereListOptions {
+	pub fn delimiter_from_name(name: &str) -> Option<char> {
+		match name.to_ascii_lowercase().as_str() {
+			"tab"   => Some('\t'),
+			"space" => Some(' '),
+			_ => {
+				let mut chars = name.chars();
+				let (Some(delimiter), None) = (chars.next(), chars.next()) else { return None };
+				Some(delimiter)
+			}
+		}
+	}
+
+	pub fn column_names_from_list(list: &str) -> Option<Vec<String>> {
+		let names: Vec<String> = list.split(',').map(|name| name.trim().to_owned()).collect();
+		names.iter().all(|name| !name.is_empty()).then_some(names)
+	}
+}
+
+// one sphere per line, "x y z r" unless a header row (or --columns) names the columns, like YADE's export.text / ymport.text
+// ("#format x_y_z_r") or a CSV with "x,y,z,radius,mass", columns other than the position and radius become attributes
+pub fn read_spheres_from_list(path: &str, options: &SphereListOptions) -> Result<YadeDemData, ReaderError> {
+
+	let file_content = fs::read_to_string(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+	let error = |msg: String, line: usize, col: usize, token: &str| ReaderError::BadToken { path: path.to_owned(), msg, line, col, token: token.to_owned() };
+
+	let mut data = YadeDemData::empty();
+	let mut delimiter = options.delimiter;
+	let mut columns = options.column_names.as_deref().map(SphereColumns::from_names).transpose().map_err(|msg| error(msg, 1, 1, ""))?;
+
+	for (line_index, line) in file_content.lines().enumerate().skip(options.skip_lines) {
+		let line_num = line_index + 1;
+
+		let trimmed = line.trim();
+		if trimmed.is_empty() { continue }
+
+		if let Some(comment) = trimmed.strip_prefix('#') {
+			// YADE's "#format x_y_z_r"
+			let format = comment.trim().strip_prefix("format").filter(|format| format.starts_with(char::is_whitespace));
+			if let (Some(format), None) = (format, &columns) {
+				let names: Vec<String> = format.trim().split('_').map(|name| name.to_owned()).collect();
+				columns = Some(SphereColumns::from_names(&names).map_err(|msg| error(msg, line_num, 1, trimmed))?);
+			}
+			continue;
+		}
+
+		let delimiter = *delimiter.get_or_insert_with(|| guess_delimiter(trimmed));
+		let tokens = split_with_delimiter(line, delimiter);
+
+		// a header row, the first line that is not all numbers
+		let is_header = data.balls.is_empty() && tokens.iter().any(|(_, token)| token.parse::<Float>().is_err());
+		if is_header {
+			if columns.is_none() {
+				let names: Vec<String> = tokens.iter().map(|(_, name)| name.to_string()).collect();
+				columns = Some(SphereColumns::from_names(&names).map_err(|msg| error(msg, line_num, 1, trimmed))?);
+			}
+			continue;
+		}
+
+		let columns = columns.get_or_insert_with(|| SphereColumns::default_for(tokens.len()));
+
+		if tokens.len() < columns.names.len() {
+			let msg = format!("expected {} values for a sphere ({}), found {}", columns.names.len(), columns.names.join(" "), tokens.len());
+			return Err(error(msg, line_num, line.trim_end().len() + 1, ""));
+		}
+
+		let parse_float = |column: usize| {
+			let (col, token) = tokens[column];
+			token.parse::<Float>().map_err(|_| error(format!("could not parse the sphere's {}", columns.names[column]), line_num, col, token))
+		};
+
+		let pos = Vec3::new(parse_float(columns.pos[0])?, parse_float(columns.pos[1])?, parse_float(columns.pos[2])?);
+		let rad = parse_float(columns.rad.0)? * columns.rad.1;
+		let attributes = columns.attributes.iter().map(|&column| parse_float(column)).collect::<Result<_, _>>()?;
+
+		let id = data.balls.len();
+		data.balls.push(Ball { pos, rad, id, attributes });
+	}
+
+	if let Some(columns) = &columns {
+		data.attribute_names = columns.attributes.iter().map(|&column| columns.names[column].clone()).collect();
+	}
+
+	Ok(data)
+}
+
+fn guess_delimiter(line: &str) -> char {
+	if line.contains(',') { return ',' }
+	if line.contains(';') { return ';' }
+	' '
+}
+
+// ' ' splits on any whitespace, other delimiters keep empty values, the columns are 1-based
+fn split_with_delimiter(line: &str, delimiter: char) -> Vec<(usize, &str)> {
+	let values: Vec<&str> = if delimiter == ' ' {
+		line.split_whitespace().collect()
+	} else {
+		line.split(delimiter).map(|value| value.trim()).collect()
+	};
+
+	values.into_iter().map(|token| (token.as_ptr() as usize - line.as_ptr() as usize + 1, token)).collect()
+}
+
+
+// where each value is in a sphere line
+struct SphereColumns {
+	names: Vec<String>,
+	pos: [usize; 3],
+	// the column and the factor to get a radius from it (diameters are halved)
+	rad: (usize, Float),
+	attributes: Vec<usize>,
+}
+
+impl SphereColumns {
+	// "x y z r", extra columns are attributes named by their 1-based index
+	fn default_for(columns_count: usize) -> Self {
+		let names = (0..columns_count.max(4)).map(|column| match column {
+			0 => "x".to_owned(),
+			1 => "y".to_owned(),
+			2 => "z".to_owned(),
+			3 => "r".to_owned(),
+			_ => format!("column {}", column + 1),
+		}).collect();
+
+		Self { names, pos: [0, 1, 2], rad: (3, 1.0), attributes: (4..columns_count).collect() }
+	}
+
+	fn from_names(names: &[String]) -> Result<Self, String> {
+		let lowercase: Vec<String> = names.iter().map(|name| name.to_ascii_lowercase()).collect();
+		let find = |aliases: &[&str]| lowercase.iter().position(|name| aliases.contains(&name.as_str()));
+
+		let mut pos = [0; 3];
+		for (column, axis) in pos.iter_mut().zip(["x", "y", "z"]) {
+			*column = find(&[axis]).ok_or_else(|| format!("the columns ({}) have no '{}'", names.join(", "), axis))?;
+		}
+
+		let rad = match (find(&["r", "rad", "radius"]), find(&["d", "diameter"])) {
+			(Some(column), _) => (column, 1.0),
+			(None, Some(column)) => (column, 0.5),
+			(None, None) => return Err(format!("the columns ({}) have no 'r' or 'diameter'", names.join(", "))),
+		};
+
+		let attributes = (0..names.len()).filter(|column| !pos.contains(column) && *column != rad.0).collect();
+
+		Ok(Self { names: names.to_vec(), pos, rad, attributes })
+	}
+}
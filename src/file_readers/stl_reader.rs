@@ -0,0 +1,168 @@
+use std::{collections::HashMap, fs};
+
+use crate::rendering::mesh::{Mesh, MeshPart, DEFAULT_CREASE_ANGLE_DEG};
+
+use super::obj_reader::{split_with_columns, ReaderError};
+
+
+const BINARY_HEADER_SIZE: usize = 80;
+const BINARY_TRI_SIZE: usize = 50;
+
+// reads ASCII and binary STL, vertices shared by triangles are welded into one
+// the normals in the file are ignored (a lot of exporters write garbage there), they are computed instead
+pub fn read_mesh_from_stl_file(path: &str) -> Result<Mesh, ReaderError> {
+
+	let file_content = fs::read(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+
+	// binary files can also start with "solid", the size telling how many triangles there are is more reliable
+	if is_binary_stl(&file_content) {
+		return Ok(read_binary_stl(&file_content));
+	}
+
+	if !file_content.starts_with(b"solid") {
+		return Err(ReaderError::bad_format("not an STL file, it's not ASCII (starting with 'solid') nor binary (size doesn't match the triangle count)".to_owned(), 1, 1));
+	}
+
+	let file_content = String::from_utf8_lossy(&file_content);
+	read_ascii_stl(&file_content)
+}
+
+pub fn is_binary_stl(file_content: &[u8]) -> bool {
+	if file_content.len() < BINARY_HEADER_SIZE + 4 { return false }
+
+	let tris_count = read_u32_le(file_content, BINARY_HEADER_SIZE) as usize;
+	file_content.len() == BINARY_HEADER_SIZE + 4 + tris_count * BINARY_TRI_SIZE
+}
+
+fn read_binary_stl(file_content: &[u8]) -> Mesh {
+	let tris_count = read_u32_le(file_content, BINARY_HEADER_SIZE) as usize;
+
+	let mut welder = VertexWelder::default();
+	let mut tris_indices = Vec::with_capacity(tris_count * 3);
+
+	for tri_i in 0..tris_count {
+		// skips the normal (12 bytes), then 3 vertices (12 bytes each), then a 2 bytes attribute
+		let tri_start = BINARY_HEADER_SIZE + 4 + tri_i * BINARY_TRI_SIZE + 12;
+
+		for vert_i in 0..3 {
+			let it = tri_start + vert_i * 12;
+			let vert = [read_f32_le(file_content, it), read_f32_le(file_content, it + 4), read_f32_le(file_content, it + 8)];
+			tris_indices.push(welder.weld(vert));
+		}
+	}
+
+	let mut mesh = Mesh::from_verts_and_indices(welder.verts, tris_indices);
+	mesh.compute_missing_normals(DEFAULT_CREASE_ANGLE_DEG);
+	mesh
+}
+
+// solid name
+//   facet normal ni nj nk
+//     outer loop
+//       vertex v1x v1y v1z
+//       vertex v2x v2y v2z
+//       vertex v3x v3y v3z
+//     endloop
+//   endfacet
+// endsolid name
+// each solid becomes a mesh part
+fn read_ascii_stl(file_content: &str) -> Result<Mesh, ReaderError> {
+
+	let mut welder = VertexWelder::default();
+	let mut tris_indices = vec![];
+	let mut parts: Vec<MeshPart> = vec![];
+
+	let mut facet_verts_count = 0;
+	let mut facet_line_num = 0;
+
+	for (line_index, line) in file_content.lines().enumerate() {
+		let line_num = line_index + 1;
+
+		let mut tokens = split_with_columns(line);
+		let Some((keyword_col, keyword)) = tokens.next() else { continue };
+
+		match keyword {
+			"solid" => {
+				let name = tokens.map(|(_, name)| name).collect::<Vec<_>>().join(" ");
+				let name = if name.is_empty() { "solid".to_owned() } else { name };
+
+				let tris_count = tris_indices.len() / 3;
+				parts.push(MeshPart { name, tris: tris_count..tris_count });
+			}
+
+			"facet" => {
+				facet_verts_count = 0;
+				facet_line_num = line_num;
+			}
+
+			"vertex" => {
+				if facet_verts_count == 3 {
+					return Err(ReaderError::bad_format("facet with more than 3 vertices".to_owned(), line_num, keyword_col));
+				}
+
+				let mut vert = [0.0; 3];
+				for coord in vert.iter_mut() {
+					let Some((col, coord_str)) = tokens.next() else {
+						return Err(ReaderError::bad_format(format!("not enough coordinates: '{}'", line), line_num, line.trim_end().len() + 1));
+					};
+					*coord = coord_str.parse::<f32>().map_err(|_| ReaderError::bad_format(format!("cant parse float '{}': '{}'", coord_str, line), line_num, col))?;
+				}
+
+				tris_indices.push(welder.weld(vert));
+				facet_verts_count += 1;
+			}
+
+			"endfacet" => {
+				if facet_verts_count != 3 {
+					return Err(ReaderError::bad_format(format!("facet has {} vertices, expected 3", facet_verts_count), facet_line_num, 1));
+				}
+
+				if let Some(part) = parts.last_mut() {
+					part.tris.end = tris_indices.len() / 3;
+				}
+			}
+
+			_ => (),
+		}
+	}
+
+	if tris_indices.len() % 3 != 0 {
+		return Err(ReaderError::bad_format("last facet is incomplete".to_owned(), facet_line_num, 1));
+	}
+
+	let mut mesh = Mesh::from_verts_and_indices(welder.verts, tris_indices);
+	mesh.parts = parts;
+	mesh.parts.retain(|part| !part.tris.is_empty());
+	mesh.compute_missing_normals(DEFAULT_CREASE_ANGLE_DEG);
+
+	Ok(mesh)
+}
+
+
+// merges vertices with exactly the same coordinates
+#[derive(Default)]
+pub struct VertexWelder {
+	pub verts: Vec<f32>,
+	index_by_vert: HashMap<[u32; 3], u32>,
+}
+
+impl VertexWelder {
+	pub fn weld(&mut self, vert: [f32; 3]) -> u32 {
+		// adding 0.0 turns -0.0 into 0.0, so both weld together
+		let key = vert.map(|coord| (coord + 0.0).to_bits());
+
+		let verts = &mut self.verts;
+		*self.index_by_vert.entry(key).or_insert_with(|| {
+			verts.extend_from_slice(&vert);
+			(verts.len() / 3 - 1) as u32
+		})
+	}
+}
+
+fn read_u32_le(bytes: &[u8], at: usize) -> u32 {
+	u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn read_f32_le(bytes: &[u8], at: usize) -> f32 {
+	f32::from_bits(read_u32_le(bytes, at))
+}
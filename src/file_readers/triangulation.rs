@@ -0,0 +1,153 @@
+use crate::maths::*;
+
+
+// splits a polygon into triangles, 'corners' are the polygon's points in order
+// returns indices into 'corners', 3 by 3, keeping the winding of the polygon
+pub fn triangulate_polygon(corners: &[Vec3]) -> Vec<usize> {
+	let corner_count = corners.len();
+	if corner_count < 3 { return vec![] }
+
+	if corner_count == 3 { return vec![0, 1, 2] }
+
+	// flattens the polygon dropping the axis its normal points the most to
+	let normal = newell_normal(corners);
+	let (abs_x, abs_y, abs_z) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+	let flat: Vec<(f32, f32)> = if abs_x >= abs_y && abs_x >= abs_z {
+		corners.iter().map(|c| c.yz()).collect()
+	} else if abs_y >= abs_z {
+		corners.iter().map(|c| (c.z, c.x)).collect()
+	} else {
+		corners.iter().map(|c| c.xy()).collect()
+	};
+
+	let winding = signed_area_2d(&flat).signum();
+
+	if winding == 0.0 || is_convex_2d(&flat, winding) {
+		return triangulate_fan(corner_count);
+	}
+
+	triangulate_ear_clipping(&flat, winding)
+}
+
+// triangulates a face of indices into 'verts' (3 floats each), pushing the triangles' indices into 'tris_indices'
+pub fn push_triangulated_face(verts: &[f32], face: &[u32], tris_indices: &mut Vec<u32>) {
+	let corners: Vec<Vec3> = face.iter().map(|&vert_index| {
+		let it = vert_index as usize * 3;
+		Vec3::new(verts[it], verts[it + 1], verts[it + 2])
+	}).collect();
+
+	for corner_index in triangulate_polygon(&corners) {
+		tris_indices.push(face[corner_index]);
+	}
+}
+
+pub fn triangulate_fan(corner_count: usize) -> Vec<usize> {
+	let mut indices = Vec::with_capacity(corner_count.saturating_sub(2) * 3);
+	for i in 1..corner_count.saturating_sub(1) {
+		indices.push(0);
+		indices.push(i);
+		indices.push(i + 1);
+	}
+
+	indices
+}
+
+// https://www.geometrictools.com/Documentation/TriangulationByEarClipping.pdf
+fn triangulate_ear_clipping(flat: &[(f32, f32)], winding: f32) -> Vec<usize> {
+	let mut remaining: Vec<usize> = (0..flat.len()).collect();
+	let mut indices = Vec::with_capacity((flat.len() - 2) * 3);
+
+	while remaining.len() > 3 {
+		let len = remaining.len();
+
+		let ear = (0..len).find(|&i| {
+			let prev = remaining[(i + len - 1) % len];
+			let curr = remaining[i];
+			let next = remaining[(i + 1) % len];
+
+			is_ear(flat, &remaining, prev, curr, next, winding)
+		});
+
+		// degenerate or self intersecting polygon, clips whatever is left as a fan
+		let Some(ear) = ear else { break };
+
+		let prev = remaining[(ear + len - 1) % len];
+		let next = remaining[(ear + 1) % len];
+		indices.push(prev);
+		indices.push(remaining[ear]);
+		indices.push(next);
+
+		remaining.remove(ear);
+	}
+
+	for i in 1..remaining.len() - 1 {
+		indices.push(remaining[0]);
+		indices.push(remaining[i]);
+		indices.push(remaining[i + 1]);
+	}
+
+	indices
+}
+
+fn is_ear(flat: &[(f32, f32)], remaining: &[usize], prev: usize, curr: usize, next: usize, winding: f32) -> bool {
+	let (a, b, c) = (flat[prev], flat[curr], flat[next]);
+
+	// reflex corners can't be ears
+	if cross_2d(a, b, c) * winding <= 0.0 { return false }
+
+	let is_any_point_inside = remaining.iter()
+		.filter(|&&i| i != prev && i != curr && i != next)
+		.any(|&i| is_point_in_tri_2d(flat[i], a, b, c, winding));
+
+	!is_any_point_inside
+}
+
+fn is_convex_2d(flat: &[(f32, f32)], winding: f32) -> bool {
+	let len = flat.len();
+	(0..len).all(|i| {
+		let a = flat[i];
+		let b = flat[(i + 1) % len];
+		let c = flat[(i + 2) % len];
+		cross_2d(a, b, c) * winding >= 0.0
+	})
+}
+
+fn is_point_in_tri_2d(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32), winding: f32) -> bool {
+	cross_2d(a, b, p) * winding >= 0.0 &&
+	cross_2d(b, c, p) * winding >= 0.0 &&
+	cross_2d(c, a, p) * winding >= 0.0
+}
+
+// z component of (b - a) x (c - b), positive when a -> b -> c turns counterclockwise
+fn cross_2d(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+	(b.0 - a.0) * (c.1 - b.1) - (b.1 - a.1) * (c.0 - b.0)
+}
+
+fn signed_area_2d(flat: &[(f32, f32)]) -> f32 {
+	let len = flat.len();
+	let mut area = 0.0;
+	for i in 0..len {
+		let (x0, y0) = flat[i];
+		let (x1, y1) = flat[(i + 1) % len];
+		area += x0 * y1 - x1 * y0;
+	}
+
+	area * 0.5
+}
+
+// works for non planar and concave polygons
+pub fn newell_normal(corners: &[Vec3]) -> Vec3 {
+	let mut normal = Vec3::zero();
+
+	let len = corners.len();
+	for i in 0..len {
+		let curr = &corners[i];
+		let next = &corners[(i + 1) % len];
+
+		normal.x += (curr.y - next.y) * (curr.z + next.z);
+		normal.y += (curr.z - next.z) * (curr.x + next.x);
+		normal.z += (curr.x - next.x) * (curr.y + next.y);
+	}
+
+	normal
+}
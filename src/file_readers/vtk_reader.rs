@@ -0,0 +1,533 @@
+use std::{fs, str::FromStr};
+
+use crate::maths::*;
+
+use super::{obj_reader::ReaderError, triangulation::triangulate_polygon, yade_dem_reader::{Ball, Tri, YadeDemData}};
+
+
+// point arrays read as the radius of balls, points are only balls if there is one of these
+const RADIUS_ARRAY_NAMES: [&str; 4] = ["radius", "radii", "rad", "r"];
+const DIAMETER_ARRAY_NAMES: [&str; 2] = ["diameter", "diameters"];
+// point arrays read as the body id, like in YADE
+const ID_ARRAY_NAMES: [&str; 2] = ["id", "ids"];
+
+// https://vtk.org/wp-content/uploads/2015/04/file-formats.pdf, ASCII POLYDATA and UNSTRUCTURED_GRID in the legacy format (.vtk)
+// or in XML (.vtp, .vtu), like YADE's VTKRecorder writes them: points with a radius array are balls, the other point arrays
+// are their attributes (the magnitude for vectors), polygons and 2D cells are facets, other cells are not drawn
+pub fn read_from_vtk_file(path: &str) -> Result<YadeDemData, ReaderError> {
+
+	let file_content = fs::read_to_string(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+
+	let pieces = if file_content.trim_start().starts_with('<') {
+		read_xml_pieces(path, &file_content)?
+	} else {
+		vec![read_legacy_piece(path, &file_content)?]
+	};
+
+	Ok(pieces_to_dem_data(&pieces))
+}
+
+
+// the points and cells of a dataset, both formats are read into this
+#[derive(Default)]
+struct VtkPiece {
+	points: Vec<Vec3>,
+	point_arrays: Vec<PointArray>,
+	// point indices of each polygon, triangles and quads included
+	polygons: Vec<Vec<usize>>,
+}
+
+struct PointArray {
+	name: String,
+	components: usize,
+	// 'components' values for each point
+	values: Vec<Float>,
+}
+
+impl PointArray {
+	// the magnitude if it has many components
+	fn value_at(&self, point_index: usize) -> Float {
+		let values = &self.values[point_index * self.components..(point_index + 1) * self.components];
+		if let [value] = values { return *value }
+
+		values.iter().map(|value| value * value).sum::<Float>().sqrt()
+	}
+
+	fn is_named(&self, names: &[&str]) -> bool {
+		names.iter().any(|name| self.name.eq_ignore_ascii_case(name))
+	}
+}
+
+impl VtkPiece {
+	// on error returns the message
+	fn validate(&self) -> Result<(), String> {
+		for array in self.point_arrays.iter() {
+			let expected_len = self.points.len() * array.components;
+			if array.values.len() != expected_len {
+				return Err(format!("the point array '{}' has {} values, expected {} ({} points, {} components)", array.name, array.values.len(), expected_len, self.points.len(), array.components));
+			}
+		}
+
+		for (polygon_i, polygon) in self.polygons.iter().enumerate() {
+			if let Some(point_i) = polygon.iter().find(|&&point_i| point_i >= self.points.len()) {
+				return Err(format!("cell {} uses point {}, but there are {} points", polygon_i, point_i, self.points.len()));
+			}
+		}
+
+		Ok(())
+	}
+
+	// VTK cell types 5 (triangle), 6 (triangle strip), 7 (polygon), 8 (pixel) and 9 (quad), 3D cells, lines and vertices are skipped
+	fn push_cell(&mut self, cell_type: u32, point_indices: &[usize]) {
+		match cell_type {
+			5 | 7 | 9 => self.polygons.push(point_indices.to_vec()),
+			6 => self.push_strip(point_indices),
+			8 if point_indices.len() == 4 => self.polygons.push(vec![point_indices[0], point_indices[1], point_indices[3], point_indices[2]]),
+			_ => (),
+		}
+	}
+
+	// every other triangle of a strip is flipped to keep the winding
+	fn push_strip(&mut self, point_indices: &[usize]) {
+		for tri_i in 0..point_indices.len().saturating_sub(2) {
+			let tri = &point_indices[tri_i..tri_i + 3];
+			let polygon = if tri_i % 2 == 0 { vec![tri[0], tri[1], tri[2]] } else { vec![tri[1], tri[0], tri[2]] };
+			self.polygons.push(polygon);
+		}
+	}
+}
+
+fn pieces_to_dem_data(pieces: &[VtkPiece]) -> YadeDemData {
+	let mut data = YadeDemData::empty();
+
+	let is_attribute = |array: &&PointArray| !array.is_named(&RADIUS_ARRAY_NAMES) && !array.is_named(&DIAMETER_ARRAY_NAMES) && !array.is_named(&ID_ARRAY_NAMES);
+	if let Some(first_piece) = pieces.first() {
+		data.attribute_names = first_piece.point_arrays.iter().filter(is_attribute).map(|array| array.name.clone()).collect();
+	}
+
+	let mut points_before = 0;
+	for piece in pieces.iter() {
+		let find_array = |names: &[&str]| piece.point_arrays.iter().find(|array| array.is_named(names));
+
+		let radius = find_array(&RADIUS_ARRAY_NAMES).map(|array| (array, 1.0)).or_else(|| find_array(&DIAMETER_ARRAY_NAMES).map(|array| (array, 0.5)));
+		let ids = find_array(&ID_ARRAY_NAMES);
+		let attribute_arrays: Vec<Option<&PointArray>> = data.attribute_names.iter().map(|name| find_array(&[name])).collect();
+
+		if let Some((radius_array, factor)) = radius {
+			for (point_i, pos) in piece.points.iter().enumerate() {
+				let attributes = attribute_arrays.iter().map(|array| array.map_or(Float::NAN, |array| array.value_at(point_i))).collect();
+				let id = ids.map_or(points_before + point_i, |ids| ids.value_at(point_i) as usize);
+				data.balls.push(Ball { pos: *pos, rad: radius_array.value_at(point_i) * factor, id, attributes });
+			}
+		}
+
+		for polygon in piece.polygons.iter() {
+			let corners: Vec<Vec3> = polygon.iter().map(|&point_i| piece.points[point_i]).collect();
+			for tri in triangulate_polygon(&corners).chunks_exact(3) {
+				let attributes = vec![Float::NAN; data.attribute_names.len()];
+				data.tris.push(Tri { p0: corners[tri[0]], p1: corners[tri[1]], p2: corners[tri[2]], attributes });
+			}
+		}
+
+		points_before += piece.points.len();
+	}
+
+	data
+}
+
+
+// legacy ".vtk", the values are read as whitespace separated tokens after the 3 header lines
+fn read_legacy_piece(path: &str, content: &str) -> Result<VtkPiece, ReaderError> {
+	let mut lines = content.lines();
+	let error = |msg: String, line: usize, col: usize, token: &str| ReaderError::BadToken { path: path.to_owned(), msg, line, col, token: token.to_owned() };
+
+	let header = lines.next().unwrap_or("");
+	if !header.trim_start().starts_with("# vtk DataFile") {
+		return Err(error("not a VTK file, it should start with '# vtk DataFile Version', found".to_owned(), 1, 1, header.trim()));
+	}
+
+	let _title = lines.next();
+	let encoding = lines.next().unwrap_or("").trim();
+	if !encoding.eq_ignore_ascii_case("ASCII") {
+		return Err(error("only ASCII VTK files are supported, found".to_owned(), 3, 1, encoding));
+	}
+
+	let mut tokens = LegacyTokens::new(path, content, 3);
+	let mut piece = VtkPiece::default();
+
+	// the size of the POINT_DATA or CELL_DATA section being read, point data are kept
+	let mut data_section: Option<(bool, usize)> = None;
+	// UNSTRUCTURED_GRID's cells, waiting for their types
+	let mut cells: Vec<Vec<usize>> = vec![];
+
+	while let Some((line, col, keyword)) = tokens.next_opt() {
+		match keyword.to_ascii_uppercase().as_str() {
+			"DATASET" => {
+				let (line, col, dataset) = tokens.next("the dataset type")?;
+				if !matches!(dataset.to_ascii_uppercase().as_str(), "POLYDATA" | "UNSTRUCTURED_GRID") {
+					return Err(error("only POLYDATA and UNSTRUCTURED_GRID datasets are supported, found".to_owned(), line, col, dataset));
+				}
+			}
+			"POINTS" => {
+				let count: usize = tokens.parse("the number of points")?;
+				tokens.next("the type of the points")?;
+
+				// the count is not reserved, a bad one would abort before a point is missing
+				for _ in 0..count {
+					let [x, y, z] = [tokens.parse("a point coordinate")?, tokens.parse("a point coordinate")?, tokens.parse("a point coordinate")?];
+					piece.points.push(Vec3::new(x, y, z));
+				}
+			}
+			"POLYGONS" | "TRIANGLE_STRIPS" | "VERTICES" | "LINES" | "CELLS" => {
+				let read_cells = tokens.read_cells()?;
+				match keyword.to_ascii_uppercase().as_str() {
+					"POLYGONS" => piece.polygons.extend(read_cells),
+					"TRIANGLE_STRIPS" => read_cells.iter().for_each(|strip| piece.push_strip(strip)),
+					"CELLS" => cells = read_cells,
+					_ => (),
+				}
+				piece.validate().map_err(|msg| error(msg, line, col, keyword))?;
+			}
+			"CELL_TYPES" => {
+				let count: usize = tokens.parse("the number of cell types")?;
+				if count != cells.len() {
+					return Err(error(format!("expected {} cell types, one for each cell, found", cells.len()), line, col, &count.to_string()));
+				}
+
+				for cell in std::mem::take(&mut cells) {
+					let cell_type = tokens.parse("a cell type")?;
+					piece.push_cell(cell_type, &cell);
+				}
+			}
+			"POINT_DATA" | "CELL_DATA" => {
+				let count: usize = tokens.parse("the number of values")?;
+				let is_point_data = keyword.eq_ignore_ascii_case("POINT_DATA");
+				if is_point_data && count != piece.points.len() {
+					return Err(error(format!("expected POINT_DATA for {} points, found", piece.points.len()), line, col, &count.to_string()));
+				}
+				data_section = Some((is_point_data, count));
+			}
+			"SCALARS" | "COLOR_SCALARS" | "VECTORS" | "NORMALS" | "TEXTURE_COORDINATES" | "TENSORS" | "FIELD" => {
+				let Some((is_point_data, count)) = data_section else {
+					return Err(error("data arrays should come after POINT_DATA or CELL_DATA, found".to_owned(), line, col, keyword));
+				};
+
+				for array in tokens.read_data_arrays(keyword, count)? {
+					if is_point_data && array.values.len() == count.saturating_mul(array.components) {
+						piece.point_arrays.push(array);
+					}
+				}
+			}
+			"LOOKUP_TABLE" => {
+				tokens.next("the name of the lookup table")?;
+				let size: usize = tokens.parse("the size of the lookup table")?;
+				// RGBA
+				tokens.skip(size.saturating_mul(4), "a lookup table color")?;
+			}
+			"METADATA" => tokens.skip_metadata(),
+			_ => return Err(error("unknown VTK keyword".to_owned(), line, col, keyword)),
+		}
+	}
+
+	piece.validate().map_err(|msg| error(msg, 1, 1, ""))?;
+
+	Ok(piece)
+}
+
+// whitespace separated tokens with their 1-based line and column
+struct LegacyTokens<'a> {
+	path: &'a str,
+	lines: Vec<&'a str>,
+	tokens: Vec<(usize, usize, &'a str)>,
+	index: usize,
+}
+
+impl<'a> LegacyTokens<'a> {
+	fn new(path: &'a str, content: &'a str, skipped_lines: usize) -> Self {
+		let lines: Vec<&str> = content.lines().collect();
+		let tokens = lines.iter().enumerate().skip(skipped_lines).flat_map(|(line_index, line)| {
+			line.split_whitespace().map(move |token| (line_index + 1, token.as_ptr() as usize - line.as_ptr() as usize + 1, token))
+		}).collect();
+
+		Self { path, lines, tokens, index: 0 }
+	}
+
+	fn error(&self, msg: String, line: usize, col: usize, token: &str) -> ReaderError {
+		ReaderError::BadToken { path: self.path.to_owned(), msg, line, col, token: token.to_owned() }
+	}
+
+	fn next_opt(&mut self) -> Option<(usize, usize, &'a str)> {
+		let token = self.tokens.get(self.index).copied();
+		self.index += 1;
+		token
+	}
+
+	fn peek(&self) -> Option<&'a str> {
+		self.tokens.get(self.index).map(|(_, _, token)| *token)
+	}
+
+	fn next(&mut self, what: &str) -> Result<(usize, usize, &'a str), ReaderError> {
+		match self.next_opt() {
+			Some(token) => Ok(token),
+			None => Err(self.error(format!("missing {} at the end of the file", what), self.lines.len() + 1, 1, "")),
+		}
+	}
+
+	fn parse<T: FromStr>(&mut self, what: &str) -> Result<T, ReaderError> {
+		let (line, col, token) = self.next(what)?;
+		token.parse().map_err(|_| self.error(format!("could not parse {}", what), line, col, token))
+	}
+
+	fn skip(&mut self, count: usize, what: &str) -> Result<(), ReaderError> {
+		for _ in 0..count { self.next(what)?; }
+		Ok(())
+	}
+
+	// METADATA blocks end at the first empty line
+	fn skip_metadata(&mut self) {
+		let Some(&(metadata_line, _, _)) = self.tokens.get(self.index.saturating_sub(1)) else { return };
+		let end_line = (metadata_line..self.lines.len()).find(|&line_index| self.lines[line_index].trim().is_empty()).map_or(usize::MAX, |line_index| line_index + 1);
+
+		while self.tokens.get(self.index).is_some_and(|&(line, _, _)| line < end_line) {
+			self.index += 1;
+		}
+	}
+
+	// "n size" and then "k i0 i1 ..." for each cell, or the "OFFSETS" and "CONNECTIVITY" arrays of version 5
+	fn read_cells(&mut self) -> Result<Vec<Vec<usize>>, ReaderError> {
+		let count: usize = self.parse("the number of cells")?;
+		let size: usize = self.parse("the size of the cells")?;
+
+		if self.peek().is_some_and(|token| token.eq_ignore_ascii_case("OFFSETS")) {
+			self.skip(2, "the type of the offsets")?;
+			let offsets = (0..count).map(|_| self.parse("a cell offset")).collect::<Result<Vec<usize>, _>>()?;
+
+			let (line, col, keyword) = self.next("CONNECTIVITY")?;
+			if !keyword.eq_ignore_ascii_case("CONNECTIVITY") {
+				return Err(self.error("expected CONNECTIVITY after the offsets, found".to_owned(), line, col, keyword));
+			}
+			self.next("the type of the connectivity")?;
+			let connectivity = (0..size).map(|_| self.parse("a cell point index")).collect::<Result<Vec<usize>, _>>()?;
+
+			return Ok(offsets.windows(2).map(|range| connectivity.get(range[0]..range[1]).unwrap_or_default().to_vec()).collect());
+		}
+
+		let mut cells = vec![];
+		for _ in 0..count {
+			let points_count: usize = self.parse("the number of points in a cell")?;
+			let cell = (0..points_count).map(|_| self.parse("a cell point index")).collect::<Result<Vec<usize>, _>>()?;
+			cells.push(cell);
+		}
+
+		Ok(cells)
+	}
+
+	// SCALARS, VECTORS and the like, with their header, 'count' is the number of points or cells
+	fn read_data_arrays(&mut self, keyword: &str, count: usize) -> Result<Vec<PointArray>, ReaderError> {
+		let keyword = keyword.to_ascii_uppercase();
+
+		if keyword == "FIELD" {
+			self.next("the name of the field")?;
+			let arrays_count: usize = self.parse("the number of arrays in the field")?;
+
+			let mut arrays = vec![];
+			for _ in 0..arrays_count {
+				let (_, _, name) = self.next("the name of an array")?;
+				let components: usize = self.parse("the number of components of an array")?;
+				let tuples: usize = self.parse("the number of tuples of an array")?;
+				self.next("the type of an array")?;
+
+				let values = self.read_values(components.saturating_mul(tuples))?;
+				arrays.push(PointArray { name: name.to_owned(), components, values });
+			}
+			return Ok(arrays);
+		}
+
+		let (_, _, name) = self.next("the name of the data")?;
+		let components = match keyword.as_str() {
+			"SCALARS" => {
+				self.next("the type of the scalars")?;
+				let components = match self.peek().and_then(|token| token.parse::<usize>().ok()) {
+					Some(components) => { self.index += 1; components }
+					None => 1,
+				};
+				if self.peek().is_some_and(|token| token.eq_ignore_ascii_case("LOOKUP_TABLE")) {
+					self.skip(2, "the lookup table")?;
+				}
+				components
+			}
+			"COLOR_SCALARS" => self.parse("the number of color components")?,
+			"TEXTURE_COORDINATES" => {
+				let dimension = self.parse("the texture coordinates dimension")?;
+				self.next("the type of the texture coordinates")?;
+				dimension
+			}
+			"TENSORS" => { self.next("the type of the tensors")?; 9 }
+			_ => { self.next("the type of the vectors")?; 3 }
+		};
+
+		let values = self.read_values(components.saturating_mul(count))?;
+		Ok(vec![PointArray { name: name.to_owned(), components, values }])
+	}
+
+	fn read_values(&mut self, count: usize) -> Result<Vec<Float>, ReaderError> {
+		(0..count).map(|_| self.parse("a data value")).collect()
+	}
+}
+
+
+// ".vtp" and ".vtu", only 'format="ascii"' arrays are supported
+fn read_xml_pieces(path: &str, content: &str) -> Result<Vec<VtkPiece>, ReaderError> {
+	let error_at = |msg: String, offset: usize, token: &str| {
+		let (line, col) = line_and_col_at(content, offset);
+		ReaderError::BadToken { path: path.to_owned(), msg, line, col, token: token.to_owned() }
+	};
+
+	let mut pieces = vec![];
+	let mut piece = VtkPiece::default();
+	let mut piece_offset = 0;
+	// names of the open elements
+	let mut stack: Vec<&str> = vec![];
+	// connectivity, offsets and types of the cells of the current element (Polys, Strips or Cells)
+	let mut cell_arrays: [Vec<usize>; 3] = Default::default();
+
+	let mut offset = 0;
+	while let Some(tag) = next_xml_tag(content, offset) {
+		offset = tag.end;
+
+		if tag.is_closing {
+			stack.pop();
+
+			match tag.name {
+				"Polys" | "Strips" | "Cells" => {
+					let [connectivity, offsets, types] = std::mem::take(&mut cell_arrays);
+					let mut start = 0;
+					for (cell_i, &end) in offsets.iter().enumerate() {
+						let point_indices = connectivity.get(start..end).ok_or_else(|| error_at(format!("the offset {} is out of the connectivity", end), tag.start, tag.name))?;
+						match tag.name {
+							"Polys"  => piece.polygons.push(point_indices.to_vec()),
+							"Strips" => piece.push_strip(point_indices),
+							_ => piece.push_cell(types.get(cell_i).copied().unwrap_or(0) as u32, point_indices),
+						}
+						start = end;
+					}
+				}
+				"Piece" => {
+					piece.validate().map_err(|msg| error_at(msg, piece_offset, "Piece"))?;
+					pieces.push(std::mem::take(&mut piece));
+				}
+				_ => (),
+			}
+			continue;
+		}
+
+		match tag.name {
+			"VTKFile" => {
+				let file_type = tag.attribute("type").unwrap_or("");
+				if !matches!(file_type, "PolyData" | "UnstructuredGrid") {
+					return Err(error_at("only PolyData and UnstructuredGrid VTK files are supported, found".to_owned(), tag.start, file_type));
+				}
+			}
+			"Piece" => piece_offset = tag.start,
+			"DataArray" => {
+				let format = tag.attribute("format").unwrap_or("ascii");
+				if format != "ascii" {
+					return Err(error_at("only ascii data arrays are supported, found".to_owned(), tag.start, format));
+				}
+
+				let text_end = content[tag.end..].find('<').map_or(content.len(), |len| tag.end + len);
+				let values: Vec<(usize, &str)> = content[tag.end..text_end].split_whitespace().map(|token| (token.as_ptr() as usize - content.as_ptr() as usize, token)).collect();
+				let parse_all = || values.iter().map(|&(token_offset, token)| token.parse::<Float>().map_err(|_| error_at("could not parse a data value".to_owned(), token_offset, token))).collect::<Result<Vec<Float>, _>>();
+
+				let name = tag.attribute("Name").unwrap_or("");
+				let components = tag.attribute("NumberOfComponents").and_then(|components| components.parse().ok()).unwrap_or(1);
+
+				match stack.last().copied() {
+					Some("Points") => {
+						let coords = parse_all()?;
+						piece.points.extend(coords.chunks_exact(3).map(|point| Vec3::new(point[0], point[1], point[2])));
+					}
+					Some("PointData") => piece.point_arrays.push(PointArray { name: name.to_owned(), components, values: parse_all()? }),
+					Some("Polys" | "Strips" | "Cells") => {
+						let array_i = match name {
+							"connectivity" => Some(0),
+							"offsets" => Some(1),
+							"types" => Some(2),
+							_ => None,
+						};
+						if let Some(array_i) = array_i {
+							cell_arrays[array_i] = parse_all()?.into_iter().map(|value| value as usize).collect();
+						}
+					}
+					_ => (),
+				}
+			}
+			_ => (),
+		}
+
+		if !tag.is_self_closing {
+			stack.push(tag.name);
+		}
+	}
+
+	if pieces.is_empty() {
+		return Err(error_at("the VTK file has no Piece".to_owned(), 0, ""));
+	}
+
+	Ok(pieces)
+}
+
+struct XmlTag<'a> {
+	name: &'a str,
+	// what's after the name, like 'type="Float32" Name="radius"'
+	attributes: &'a str,
+	is_closing: bool,
+	is_self_closing: bool,
+	// byte offsets of the '<' and after the '>'
+	start: usize,
+	end: usize,
+}
+
+impl<'a> XmlTag<'a> {
+	fn attribute(&self, name: &str) -> Option<&'a str> {
+		let mut rest = self.attributes;
+		while let Some(equals_i) = rest.find('=') {
+			let key = rest[..equals_i].trim();
+			let after_equals = rest[equals_i + 1..].trim_start();
+
+			let quote = after_equals.chars().next().filter(|&quote| quote == '"' || quote == '\'')?;
+			let value_end = after_equals[1..].find(quote)? + 1;
+			if key == name { return Some(&after_equals[1..value_end]) }
+
+			rest = &after_equals[value_end + 1..];
+		}
+		None
+	}
+}
+
+// skips "<?xml ... ?>" and comments
+fn next_xml_tag(content: &str, from: usize) -> Option<XmlTag<'_>> {
+	let mut start = from + content[from..].find('<')?;
+
+	while content[start..].starts_with("<?") || content[start..].starts_with("<!") {
+		let closing = if content[start..].starts_with("<!--") { "-->" } else { ">" };
+		let end = start + content[start..].find(closing)? + closing.len();
+		start = end + content[end..].find('<')?;
+	}
+
+	let end = start + content[start..].find('>')? + 1;
+	let inner = &content[start + 1..end - 1];
+
+	let is_closing = inner.starts_with('/');
+	let is_self_closing = inner.ends_with('/');
+	let inner = inner.trim_start_matches('/').trim_end_matches('/');
+
+	let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+	Some(XmlTag { name: &inner[..name_end], attributes: &inner[name_end..], is_closing, is_self_closing, start, end })
+}
+
+fn line_and_col_at(content: &str, offset: usize) -> (usize, usize) {
+	let before = &content[..offset.min(content.len())];
+	let line_start = before.rfind('\n').map_or(0, |newline_i| newline_i + 1);
+	(before.matches('\n').count() + 1, offset - line_start + 1)
+}
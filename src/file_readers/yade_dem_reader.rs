@@ -1,13 +1,24 @@
-use std::{fmt::Display, fs, io::Write, process};
+use std::{fmt::Display, fs::{self, File}, io::{Read, Write}, sync::{atomic::{AtomicBool, Ordering}, mpsc::{self, SyncSender}, Mutex}, thread};
 
 use crate::maths::*;
 
-const YADE_SCALE: f32 = 15.0;
+use super::{loading_progress::LoadingProgress, obj_reader::ReaderError};
 
 
+// files are read and parsed in pieces of about this size, cut at line ends
+const CHUNK_LEN: usize = 4 << 20;
+
+
+// read in file units, 'WorldTransform' converts them to world coordinates
+#[derive(Clone)]
 pub struct YadeDemData {
 	pub tris:  Vec<Tri>,
 	pub balls: Vec<Ball>,
+	pub cuboids:   Vec<Cuboid>,
+	pub walls:     Vec<Wall>,
+	pub cylinders: Vec<Cylinder>,
+	// names of the extra columns given by a header row, every body has one value for each
+	pub attribute_names: Vec<String>,
 }
 
 
@@ -15,6 +26,10 @@ pub struct YadeDemData {
 pub struct Ball {
 	pub pos: Vec3,
 	pub rad: Float,
+	// YADE body id, the order of the body in the file counting every shape
+	pub id: usize,
+	// same order as 'YadeDemData::attribute_names'
+	pub attributes: Vec<Float>,
 }
 
 impl Display for Ball {
@@ -23,19 +38,45 @@ impl Display for Ball {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Tri {
 	pub p0: Vec3,
 	pub p1: Vec3,
 	pub p2: Vec3,
+	// same order as 'YadeDemData::attribute_names', NaN if the header has no facet columns
+	pub attributes: Vec<Float>,
 }
 
 impl Tri {
 	fn with_pos(p0: Vec3, p1: Vec3, p2: Vec3) -> Tri {
-		Tri { p0, p1, p2 }
+		Tri { p0, p1, p2, attributes: vec![] }
 	}
 }
 
+// YADE's Box, aligned to the axes
+#[derive(Debug, Clone)]
+pub struct Cuboid {
+	pub center: Vec3,
+	// half of the size in each axis
+	pub extents: Vec3,
+}
+
+// YADE's infinite Wall, a plane perpendicular to an axis
+#[derive(Debug, Clone)]
+pub struct Wall {
+	pub pos: Vec3,
+	// 0, 1 or 2 for X, Y or Z
+	pub axis: usize,
+}
+
+// YADE's GridConnection (or any cylinder), a capsule between two points
+#[derive(Debug, Clone)]
+pub struct Cylinder {
+	pub p0: Vec3,
+	pub p1: Vec3,
+	pub rad: Float,
+}
+
 impl YadeDemData {
 
 	pub fn debug() -> Self {
@@ -67,103 +108,144 @@ impl YadeDemData {
 		tris.push(Tri::with_pos(v4, v0, v7));
 
 		// balls.push(Ball { pos: Vec3 { x:  0.00, y:  0.00, z:  0.00 }, rad: 0.15 });
-		balls.push(Ball { pos: Vec3 { x:  0.75, y:  0.00, z:  0.00 }, rad: 0.15 });
+		balls.push(Ball { pos: Vec3 { x:  0.75, y:  0.00, z:  0.00 }, rad: 0.15, id: 0, attributes: vec![] });
 		// balls.push(Ball { pos: Vec3 { x:  0.00, y:  0.75, z:  0.00 }, rad: 0.15 });
 		// balls.push(Ball { pos: Vec3 { x:  0.00, y:  0.00, z:  0.75 }, rad: 0.15 });
-		balls.push(Ball { pos: Vec3 { x: -0.75, y:  0.00, z:  0.00 }, rad: 0.15 });
+		balls.push(Ball { pos: Vec3 { x: -0.75, y:  0.00, z:  0.00 }, rad: 0.15, id: 1, attributes: vec![] });
 		// balls.push(Ball { pos: Vec3 { x:  0.00, y: -0.75, z:  0.00 }, rad: 0.15 });
 		// balls.push(Ball { pos: Vec3 { x:  0.00, y:  0.00, z: -0.75 }, rad: 0.15 });
 
 		Self {
 			balls,
 			tris,
+			..Self::empty()
 		}
 	}
 
-	pub fn read_from_file_or_quit(path: &str) -> Self {
-		// println!("Reading file '{}'", path);
+	pub fn empty() -> Self {
+		Self {
+			tris: vec![],
+			balls: vec![],
+			cuboids: vec![],
+			walls: vec![],
+			cylinders: vec![],
+			attribute_names: vec![],
+		}
+	}
 
-		// let file_content = fs::read_to_string(path).map_err(|err| err.to_string())?;
-		let file_content = match fs::read_to_string(path) {
-			Ok(content) => content,
-			Err(error) => {
-				eprintln!("IO error: {}", error);
-				process::exit(1)
-			}
+	// min and max corners of every body but the walls (they are infinite), None if there are none
+	pub fn get_bounds(&self) -> Option<(Vec3, Vec3)> {
+		let mut min = Vec3::new(f32::MAX, f32::MAX, f32::MAX);
+		let mut max = Vec3::new(f32::MIN, f32::MIN, f32::MIN);
+
+		let mut expand = |pos: &Vec3, half_size: &Vec3| {
+			min = Vec3::new(min.x.min(pos.x - half_size.x), min.y.min(pos.y - half_size.y), min.z.min(pos.z - half_size.z));
+			max = Vec3::new(max.x.max(pos.x + half_size.x), max.y.max(pos.y + half_size.y), max.z.max(pos.z + half_size.z));
 		};
 
-		let mut balls = vec![];
-		let mut tris  = vec![];
+		for ball in self.balls.iter() {
+			expand(&ball.pos, &Vec3::new(ball.rad, ball.rad, ball.rad));
+		}
 
-		for (line_index, line) in file_content.lines().enumerate() {
-			let line_num = line_index + 1;
-			// println!(" {}: '{}'", i, line);
+		for tri in self.tris.iter() {
+			for point in [&tri.p0, &tri.p1, &tri.p2] {
+				expand(point, &Vec3::zero());
+			}
+		}
 
-			let line = line.trim();
-			let mut line_split = line.split(',').skip(1);
+		for cuboid in self.cuboids.iter() {
+			expand(&cuboid.center, &cuboid.extents);
+		}
 
-			let is_sphere = line.starts_with('0');
-			if is_sphere {
+		for cylinder in self.cylinders.iter() {
+			let rad = Vec3::new(cylinder.rad, cylinder.rad, cylinder.rad);
+			expand(&cylinder.p0, &rad);
+			expand(&cylinder.p1, &rad);
+		}
 
-				// TODO: port to juicier Rust
-				// let [ x, y, z, rad ] = line_split.next_chunk().unwrap();
+		let is_empty = min.x > max.x;
+		if is_empty { return None }
 
-				let   x = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let   y = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let   z = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let rad = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
+		Some((min, max))
+	}
 
-				// input coordinate system is XYZ, converts to XZY
-				let ball = Ball {
-					pos: Vec3 { x: x, y: z, z: y }.scale(YADE_SCALE),
-					rad: rad * YADE_SCALE
-				};
-				// println!(" got 0: {:?} ", ball);
+	// stops at the first bad line
+	pub fn read_from_file(path: &str) -> Result<Self, ReaderError> {
+		Self::read_from_file_with(path, BadLinesMode::StopAtFirst)
+	}
 
-				balls.push(ball);
-				continue;
-			}
+	// the file is read in chunks that are parsed on every core and then put together in order,
+	// big files show their progress on stderr
+	pub fn read_from_file_with(path: &str, bad_lines_mode: BadLinesMode) -> Result<Self, ReaderError> {
 
-			let is_triangle = line.starts_with('1');
-			if is_triangle {
-
-				// TODO: port to juicier Rust
-				// let [ x, y, z, p0x, p0y, p0z, p1x, p1y, p1z, p2x, p2y, p2z ] = line_split.next_chunk().unwrap();
-
-				let   x = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let   y = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let   z = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p0x = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p0y = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p0z = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p1x = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p1y = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p1z = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p2x = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p2y = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-				let p2z = get_next_float_in_line_or_quit(&mut line_split, path, line_num);
-
-				// input coordinate system is XYZ, converts to XZY
-				let pos = Vec3 { x: x, y: z, z: y }.scale(YADE_SCALE);
-				let tri = Tri {
-					p0: Vec3 { x: p0x, y: p0z, z: p0y }.scale(YADE_SCALE).add_vec(&pos),
-					p1: Vec3 { x: p1x, y: p1z, z: p1y }.scale(YADE_SCALE).add_vec(&pos),
-					p2: Vec3 { x: p2x, y: p2z, z: p2y }.scale(YADE_SCALE).add_vec(&pos),
-				};
-				// println!(" got 1: {:?} ", tri);
+		let file = File::open(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+		let file_len = file.metadata().map_err(|err| ReaderError::from_io_error(err, path))?.len();
+		let mut chunks = ChunkReader { path, file, leftover: vec![], next_index: 0, next_line_num: 1 };
 
-				// input coordinate system is XYZ, converts to XZY
-				tris.push(tri);
+		let mut data = Self::empty();
+		let mut columns = YadeColumns::default();
+		let first_chunk = chunks.read_header(&mut columns, &mut data.attribute_names)?;
 
-				continue;
-			}
+		let progress = LoadingProgress::new(path, file_len);
+		let threads_count = thread::available_parallelism().map_or(1, |count| count.get());
+		let stop_at_first = matches!(bad_lines_mode, BadLinesMode::StopAtFirst);
+		let has_failed = AtomicBool::new(false);
+
+		let (chunks_sender, chunks_receiver) = mpsc::sync_channel::<Chunk>(threads_count * 2);
+		let chunks_receiver = Mutex::new(chunks_receiver);
+
+		let (read_result, mut parsed_chunks) = thread::scope(|scope| {
+			let workers: Vec<_> = (0..threads_count).map(|_| scope.spawn(|| {
+				let mut parsed_chunks = vec![];
+				loop {
+					// received in its own statement, so the lock is released before the chunk is parsed
+					let Ok(chunk) = chunks_receiver.lock().unwrap().recv() else { break };
+
+					let parsed = chunk.parse(path, &columns, stop_at_first);
+					if stop_at_first && !parsed.errors.is_empty() {
+						has_failed.store(true, Ordering::Relaxed);
+					}
 
-			if line.is_empty() { continue; }
+					progress.add(chunk.text.len(), chunk.lines_count);
+					parsed_chunks.push(parsed);
+				}
+				parsed_chunks
+			})).collect();
 
-			let is_comment = line.starts_with('#') || line.starts_with("//");
-			if is_comment { continue; }
+			let reporter = progress.is_reported().then(|| scope.spawn(|| progress.report_until_done()));
 
-			quit_with(&format!("unexpected error reading line {}", line_num), path);
+			let read_result = chunks.send_all(first_chunk, chunks_sender, &has_failed);
+
+			let parsed_chunks: Vec<ParsedChunk> = workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect();
+
+			progress.finish();
+			if let Some(reporter) = reporter {
+				reporter.thread().unpark();
+			}
+
+			(read_result, parsed_chunks)
+		});
+		read_result?;
+
+		parsed_chunks.sort_by_key(|parsed| parsed.index);
+
+		let mut errors = vec![];
+		for parsed in parsed_chunks {
+			// the ids are the order of the bodies in the whole file
+			let id_offset = data.bodies_count();
+			data.balls.extend(parsed.data.balls.into_iter().map(|ball| Ball { id: ball.id + id_offset, ..ball }));
+			data.tris.extend(parsed.data.tris);
+			data.cuboids.extend(parsed.data.cuboids);
+			data.walls.extend(parsed.data.walls);
+			data.cylinders.extend(parsed.data.cylinders);
+			errors.extend(parsed.errors);
+		}
+
+		if !errors.is_empty() {
+			return match bad_lines_mode {
+				BadLinesMode::StopAtFirst => Err(errors.swap_remove(0)),
+				BadLinesMode::CollectAll => Err(ReaderError::Many(errors)),
+			};
 		}
 
 		// #if VERBOSE
@@ -181,14 +263,91 @@ impl YadeDemData {
 
 		const LOG_FILE_PATH: &str = "bullshit/_log.txt";
 		if let Ok(mut file) = fs::File::create(LOG_FILE_PATH).map(std::io::BufWriter::new) {
-			let fmt = format!("file at '{}':\n{} balls\n{} tris\n", path, balls.len(), tris.len());
+			let fmt = format!("file at '{}':\n{} balls\n{} tris\n{} boxes\n{} walls\n{} cylinders\n", path, data.balls.len(), data.tris.len(), data.cuboids.len(), data.walls.len(), data.cylinders.len());
 			let _ = file.write_all(fmt.as_bytes());
 		}
 
-		Self {
-			balls,
-			tris,
+		Ok(data)
+	}
+
+	pub fn bodies_count(&self) -> usize {
+		self.balls.len() + self.tris.len() + self.cuboids.len() + self.walls.len() + self.cylinders.len()
+	}
+
+	// on error returns the message, the 1-based column and the bad token
+	// a line after the first body, the header row was read by 'ChunkReader::read_header'
+	fn read_line<'a>(&mut self, line: &'a str, columns: &YadeColumns) -> Result<(), (String, usize, &'a str)> {
+
+		let trimmed = line.trim();
+		if is_skipped_line(trimmed) { return Ok(()) }
+
+		if is_header_row(trimmed) {
+			return Err(("the header row must come before every body".to_owned(), 1, trimmed));
+		}
+
+		let values = LineValues::new(line);
+		let (id_col, id) = values.tokens[0];
+
+		match id {
+			"0" => {
+				let [x, y, z, rad] = values.floats_at(&columns.ball, "sphere")?;
+				let attributes = values.floats_at_vec(&columns.attributes, "sphere")?;
+
+				let ball = Ball {
+					pos: Vec3::new(x, y, z),
+					rad,
+					id: self.bodies_count(),
+					attributes,
+				};
+
+				self.balls.push(ball);
+			}
+
+			"1" => {
+				let [x, y, z, p0x, p0y, p0z, p1x, p1y, p1z, p2x, p2y, p2z] = values.floats_at(&columns.tri, "triangle")?;
+				let attributes = if columns.tri_has_attributes {
+					values.floats_at_vec(&columns.attributes, "triangle")?
+				} else {
+					vec![Float::NAN; columns.attributes.len()]
+				};
+
+				// the points are relative to the facet's position
+				let pos = Vec3::new(x, y, z);
+				let tri = Tri {
+					p0: Vec3::new(p0x, p0y, p0z).add_vec(&pos),
+					p1: Vec3::new(p1x, p1y, p1z).add_vec(&pos),
+					p2: Vec3::new(p2x, p2y, p2z).add_vec(&pos),
+					attributes,
+				};
+
+				self.tris.push(tri);
+			}
+
+			"2" => {
+				let [x, y, z, ex, ey, ez] = values.floats_at(&[1, 2, 3, 4, 5, 6], "box")?;
+				self.cuboids.push(Cuboid { center: Vec3::new(x, y, z), extents: Vec3::new(ex, ey, ez) });
+			}
+
+			"3" => {
+				// the sense (which side of the wall interacts) doesn't matter to draw it
+				let [x, y, z, axis] = values.floats_at(&[1, 2, 3, 4], "wall")?;
+				if !matches!(axis, 0.0 | 1.0 | 2.0) {
+					let (axis_col, axis_token) = values.tokens[4];
+					return Err(("the axis of a wall should be 0, 1 or 2, found".to_owned(), axis_col, axis_token));
+				}
+
+				self.walls.push(Wall { pos: Vec3::new(x, y, z), axis: axis as usize });
+			}
+
+			"4" => {
+				let [x0, y0, z0, x1, y1, z1, rad] = values.floats_at(&[1, 2, 3, 4, 5, 6, 7], "cylinder")?;
+				self.cylinders.push(Cylinder { p0: Vec3::new(x0, y0, z0), p1: Vec3::new(x1, y1, z1), rad });
+			}
+
+			_ => return Err(("unknown shape, expected 0 (sphere), 1 (triangle), 2 (box), 3 (wall) or 4 (cylinder), found".to_owned(), id_col, id)),
 		}
+
+		Ok(())
 	}
 
 	pub fn print_mock() {
@@ -270,29 +429,300 @@ impl<'a> Iterator for TrianglesVec3Iterator<'a> {
 }
 
 
-fn get_next_float_in_line_or_quit<'a>(line_iter: &mut impl Iterator<Item = &'a str>, path: &str, line_num: usize) -> Float {
+#[derive(Clone, Copy)]
+pub enum BadLinesMode {
+	StopAtFirst,
+	// reads the whole file and reports every bad line
+	CollectAll,
+}
+
+// empty lines, "//" comments and "#" comments that are not the header row
+fn is_skipped_line(trimmed: &str) -> bool {
+	trimmed.is_empty() || trimmed.starts_with("//") || (trimmed.starts_with('#') && !is_header_row(trimmed))
+}
 
-	let next_str = match line_iter.next() {
-		None => quit_with(&format!("Not enough coordinates at line {line_num}"), path),
-		Some(slice) => slice,
-	};
+// "# type, x, y, z, r, ..."
+fn is_header_row(trimmed: &str) -> bool {
+	trimmed.strip_prefix('#').is_some_and(|header| header.split(',').next().is_some_and(|first| first.trim().eq_ignore_ascii_case("type")))
+}
 
-	let trimmed_str = next_str.trim();
-	if trimmed_str.is_empty() {
-		quit_with(&format!("Empty string slice, should have a value, line: {line_num}"), path);
+// a line of a chunk without its line end
+fn line_to_str<'a>(line: &'a [u8], path: &str, line_num: usize) -> Result<&'a str, ReaderError> {
+	match std::str::from_utf8(line) {
+		Ok(line) => Ok(line.trim_end_matches(['\n', '\r'])),
+		Err(err) => Err(ReaderError::BadToken { path: path.to_owned(), msg: "the line is not UTF-8 text".to_owned(), line: line_num, col: err.valid_up_to() + 1, token: String::new() }),
 	}
+}
+
+
+// reads YADE lines one at a time into frames, for streams that don't end (stdin, named pipes)
+pub struct YadeLineReader {
+	columns: YadeColumns,
+	// of the frame being read
+	data: YadeDemData,
+}
 
-	match trimmed_str.parse() {
-		Err(err) => quit_with(&format!("Could not parse a float from string slice: '{trimmed_str}', line: {line_num}\nerr: {err}"), path),
-		Ok(float) => float,
+impl Default for YadeLineReader {
+	fn default() -> Self {
+		Self { columns: YadeColumns::default(), data: YadeDemData::empty() }
 	}
 }
 
-// OR with generics:
-// fn next_float_or_quit<'a, T>(it: &mut T, line_num: usize) -> Float where T: Iterator<Item = &'a str> { 0.0 }
+impl YadeLineReader {
+	// a header row before the first body of a frame applies to it and the frames after it
+	pub fn read_line<'a>(&mut self, line: &'a str) -> Result<(), (String, usize, &'a str)> {
+		if is_header_row(line.trim()) && self.data.bodies_count() == 0 {
+			(self.columns, self.data.attribute_names) = YadeColumns::from_header_row(line)?;
+			return Ok(());
+		}
+
+		self.data.read_line(line, &self.columns)
+	}
+
+	// the bodies read since the last frame, the attribute names are kept for the next one
+	pub fn take_frame(&mut self) -> YadeDemData {
+		let next_frame = YadeDemData { attribute_names: self.data.attribute_names.clone(), ..YadeDemData::empty() };
+		std::mem::replace(&mut self.data, next_frame)
+	}
+}
+
+
+// whole lines of a file
+struct Chunk {
+	// in the file, chunks are parsed out of order
+	index: usize,
+	first_line_num: usize,
+	lines_count: usize,
+	text: Vec<u8>,
+}
+
+impl Chunk {
+	// the ids of the bodies start at 0, stops at the first bad line if 'stop_at_first'
+	fn parse(&self, path: &str, columns: &YadeColumns, stop_at_first: bool) -> ParsedChunk {
+		let mut data = YadeDemData::empty();
+		let mut errors = vec![];
+
+		for (line_index, line) in self.text.split(|&byte| byte == b'\n').enumerate() {
+			let line_num = self.first_line_num + line_index;
+
+			let read_result = line_to_str(line, path, line_num).and_then(|line| data.read_line(line, columns).map_err(|(msg, col, token)|
+				ReaderError::BadToken { path: path.to_owned(), msg, line: line_num, col, token: token.to_owned() }
+			));
+
+			if let Err(err) = read_result {
+				errors.push(err);
+				if stop_at_first { break }
+			}
+		}
+
+		ParsedChunk { index: self.index, data, errors }
+	}
+}
+
+struct ParsedChunk {
+	index: usize,
+	data: YadeDemData,
+	errors: Vec<ReaderError>,
+}
+
+// reads a file in chunks of about 'CHUNK_LEN' cut at line ends
+struct ChunkReader<'a> {
+	path: &'a str,
+	file: File,
+	// the start of the line cut by the last read
+	leftover: Vec<u8>,
+	next_index: usize,
+	next_line_num: usize,
+}
+
+impl ChunkReader<'_> {
+	// None at the end of the file
+	fn next_chunk(&mut self) -> Result<Option<Chunk>, ReaderError> {
+		let mut text = std::mem::take(&mut self.leftover);
+
+		loop {
+			let read_len = (&mut self.file).take(CHUNK_LEN as u64).read_to_end(&mut text).map_err(|err| ReaderError::from_io_error(err, self.path))?;
+			if read_len == 0 {
+				if text.is_empty() { return Ok(None) }
+				break;
+			}
+
+			// a line longer than a chunk makes it read more
+			if let Some(last_line_end) = text.iter().rposition(|&byte| byte == b'\n') {
+				self.leftover = text.split_off(last_line_end + 1);
+				break;
+			}
+		}
+
+		let lines_count = text.iter().filter(|&&byte| byte == b'\n').count();
+		let chunk = Chunk { index: self.next_index, first_line_num: self.next_line_num, lines_count, text };
+
+		self.next_index += 1;
+		self.next_line_num += lines_count;
+
+		Ok(Some(chunk))
+	}
+
+	// reads the lines before the first body, where the header row can be, and returns the rest of their chunk,
+	// None if there are no bodies
+	fn read_header(&mut self, columns: &mut YadeColumns, attribute_names: &mut Vec<String>) -> Result<Option<Chunk>, ReaderError> {
+		while let Some(mut chunk) = self.next_chunk()? {
+			let mut first_body = None;
+			let mut line_start = 0;
+
+			for (line_index, line) in chunk.text.split_inclusive(|&byte| byte == b'\n').enumerate() {
+				let line_num = chunk.first_line_num + line_index;
+				let line_str = line_to_str(line, self.path, line_num)?;
+				let trimmed = line_str.trim();
+
+				if is_header_row(trimmed) {
+					(*columns, *attribute_names) = YadeColumns::from_header_row(line_str).map_err(|(msg, col, token)|
+						ReaderError::BadToken { path: self.path.to_owned(), msg, line: line_num, col, token: token.to_owned() }
+					)?;
+				} else if !is_skipped_line(trimmed) {
+					first_body = Some((line_start, line_index));
+					break;
+				}
+
+				line_start += line.len();
+			}
+
+			if let Some((line_start, line_index)) = first_body {
+				chunk.text.drain(..line_start);
+				chunk.first_line_num += line_index;
+				chunk.lines_count -= line_index;
+				return Ok(Some(chunk));
+			}
+		}
+
+		Ok(None)
+	}
+
+	// 'first_chunk' and the rest of the file, until the end or until a chunk 'has_failed'
+	fn send_all(&mut self, first_chunk: Option<Chunk>, sender: SyncSender<Chunk>, has_failed: &AtomicBool) -> Result<(), ReaderError> {
+		let Some(first_chunk) = first_chunk else { return Ok(()) };
+		// the workers only stop receiving when they panic
+		let _ = sender.send(first_chunk);
 
-fn quit_with(message: &str, path: &str) -> ! {
-	eprintln!("Error reading '{path}'");
-	eprintln!("{}", message);
-	std::process::exit(1);
-}
\ No newline at end of file
+		while !has_failed.load(Ordering::Relaxed) {
+			let Some(chunk) = self.next_chunk()? else { break };
+			let _ = sender.send(chunk);
+		}
+
+		Ok(())
+	}
+}
+
+// where each value is in a line, by default spheres are "0, x, y, z, r" and facets "1, x, y, z, p0x, p0y, p0z, p1x, ..., p2z"
+// (the points are relative to x, y, z), a header row like "# type, x, y, z, r, vx, vy, vz, mat" changes it
+// boxes "2, x, y, z, ex, ey, ez", walls "3, x, y, z, axis, sense" and cylinders "4, x0, y0, z0, x1, y1, z1, r" always use their default layout
+struct YadeColumns {
+	// x, y, z, r
+	ball: [usize; 4],
+	// x, y, z, p0x, p0y, p0z, p1x, p1y, p1z, p2x, p2y, p2z
+	tri: [usize; 12],
+	// extra columns, read as attributes of the bodies
+	attributes: Vec<usize>,
+	// false if the header has no facet columns, then facets keep the default layout
+	tri_has_attributes: bool,
+}
+
+impl Default for YadeColumns {
+	fn default() -> Self {
+		Self {
+			ball: [1, 2, 3, 4],
+			tri: std::array::from_fn(|i| i + 1),
+			attributes: vec![],
+			tri_has_attributes: false,
+		}
+	}
+}
+
+impl YadeColumns {
+	const BALL_NAMES: [&'static [&'static str]; 4] = [&["x"], &["y"], &["z"], &["r", "rad", "radius"]];
+	const TRI_NAMES: [&'static str; 12] = ["x", "y", "z", "p0x", "p0y", "p0z", "p1x", "p1y", "p1z", "p2x", "p2y", "p2z"];
+
+	// and the names of the attribute columns
+	fn from_header_row(line: &str) -> Result<(Self, Vec<String>), (String, usize, &str)> {
+		let header = LineValues::new(line);
+		let columns = Self::from_header(&header)?;
+		let attribute_names = columns.attributes.iter().map(|&column| header.tokens[column].1.to_owned()).collect();
+
+		Ok((columns, attribute_names))
+	}
+
+	fn from_header<'a>(header: &LineValues<'a>) -> Result<Self, (String, usize, &'a str)> {
+		// the first token has the '#'
+		let names: Vec<String> = header.tokens.iter().map(|(_, name)| name.trim_start_matches('#').trim().to_ascii_lowercase()).collect();
+		let find = |name: &str| names.iter().position(|column_name| column_name == name);
+
+		let mut ball = [0; 4];
+		for (column, aliases) in ball.iter_mut().zip(Self::BALL_NAMES) {
+			let Some(found) = aliases.iter().find_map(|alias| find(alias)) else {
+				return Err((format!("the header has no '{}' column", aliases[0]), header.end_col(), ""));
+			};
+			*column = found;
+		}
+
+		let tri_columns: Vec<Option<usize>> = Self::TRI_NAMES.iter().map(|name| find(name)).collect();
+		let tri_has_attributes = tri_columns.iter().all(|column| column.is_some());
+
+		let mut tri = Self::default().tri;
+		if tri_has_attributes {
+			tri = std::array::from_fn(|i| tri_columns[i].unwrap());
+		}
+
+		let is_geometry = |column: usize| column == 0 || ball.contains(&column) || (tri_has_attributes && tri.contains(&column));
+		let attributes = (0..names.len()).filter(|&column| !is_geometry(column)).collect();
+
+		Ok(Self { ball, tri, attributes, tri_has_attributes })
+	}
+}
+
+// comma separated values of a line with their 1-based columns
+struct LineValues<'a> {
+	line: &'a str,
+	tokens: Vec<(usize, &'a str)>,
+}
+
+impl<'a> LineValues<'a> {
+	fn new(line: &'a str) -> Self {
+		let tokens = line.split(',').map(|value| {
+			let token = value.trim();
+			let col = token.as_ptr() as usize - line.as_ptr() as usize + 1;
+			(col, token)
+		}).collect();
+
+		Self { line, tokens }
+	}
+
+	// column after the end of the line
+	fn end_col(&self) -> usize {
+		self.line.trim_end().len() + 1
+	}
+
+	fn float_at(&self, index: usize, shape: &str) -> Result<Float, (String, usize, &'a str)> {
+		let is_last = index + 1 == self.tokens.len();
+		let (col, token) = match self.tokens.get(index) {
+			Some(&(col, token)) if !token.is_empty() => (col, token),
+			// "0, 1,, 3, 0.1", an empty last value (a trailing comma) is reported as missing below
+			Some(&(col, _)) if !is_last => return Err((format!("empty value for a {}, expected a float between the commas", shape), col, "")),
+			_ => return Err((format!("not enough values for a {}, expected at least {} after the shape id", shape, index), self.end_col(), "")),
+		};
+
+		token.parse().map_err(|_| (format!("could not parse a float for a {}", shape), col, token))
+	}
+
+	fn floats_at<const N: usize>(&self, indices: &[usize; N], shape: &str) -> Result<[Float; N], (String, usize, &'a str)> {
+		let mut floats = [0.0; N];
+		for (float, &index) in floats.iter_mut().zip(indices) {
+			*float = self.float_at(index, shape)?;
+		}
+
+		Ok(floats)
+	}
+
+	fn floats_at_vec(&self, indices: &[usize], shape: &str) -> Result<Vec<Float>, (String, usize, &'a str)> {
+		indices.iter().map(|&index| self.float_at(index, shape)).collect()
+	}
+}
@@ -0,0 +1,121 @@
+use std::{fs, path::Path};
+
+use crate::maths::*;
+
+use super::{obj_reader::ReaderError, yade_timeline::{list_frame_paths, YadeTimeline}};
+
+
+// a contact between two bodies, by their YADE ids
+pub struct Interaction {
+	pub id0: usize,
+	pub id1: usize,
+	pub normal_force: Float,
+	// 0 if the file has no shear force
+	pub shear_force: Float,
+}
+
+impl Interaction {
+	pub fn force(&self) -> Float {
+		self.normal_force.hypot(self.shear_force)
+	}
+}
+
+// contacts weaker than 'min' are hidden, 'max' and stronger ones are drawn the brightest
+#[derive(Clone, Copy)]
+pub struct ForceThresholds {
+	pub min: Float,
+	// None uses the strongest contact of the snapshot
+	pub max: Option<Float>,
+}
+
+impl Default for ForceThresholds {
+	fn default() -> Self {
+		Self { min: 0.0, max: None }
+	}
+}
+
+// contacts of a snapshot, each line is "id0, id1, fn", "id0, id1, fn, fs", "id0, id1, fnx, fny, fnz"
+// or "id0, id1, fnx, fny, fnz, fsx, fsy, fsz", vectors are read as their magnitude
+pub struct YadeInteractions {
+	pub interactions: Vec<Interaction>,
+	pub max_force: Float,
+}
+
+impl YadeInteractions {
+	// one for each snapshot of 'timeline', a single file is used for all of them
+	pub fn read_for_timeline(source: &str, timeline: &YadeTimeline) -> Result<Vec<Self>, ReaderError> {
+		if !YadeTimeline::is_sequence_source(source) {
+			return Ok(vec![Self::read_from_file(source)?]);
+		}
+
+		// interactions start with two ids, not a shape, so they don't look like YADE files
+		let paths = list_frame_paths(source, |_| true)?;
+		if paths.len() != timeline.len() {
+			let msg = format!("expected {} interaction files, one for each snapshot, found {}", timeline.len(), paths.len());
+			return Err(ReaderError::BadToken { path: source.to_owned(), msg, line: 1, col: 1, token: String::new() });
+		}
+
+		paths.iter().map(|(path, _)| Self::read_from_file(path)).collect()
+	}
+
+	pub fn read_from_file(path: &str) -> Result<Self, ReaderError> {
+		if !Path::new(path).is_file() {
+			return Err(ReaderError::FileNotFound(path.to_owned()));
+		}
+
+		let file_content = fs::read_to_string(path).map_err(|err| ReaderError::from_io_error(err, path))?;
+
+		let mut interactions = vec![];
+		for (line_index, line) in file_content.lines().enumerate() {
+			let trimmed = line.trim();
+			let is_comment = trimmed.starts_with('#') || trimmed.starts_with("//");
+			if trimmed.is_empty() || is_comment { continue }
+
+			let interaction = read_interaction(line).map_err(|(msg, col, token)|
+				ReaderError::BadToken { path: path.to_owned(), msg, line: line_index + 1, col, token: token.to_owned() }
+			)?;
+			interactions.push(interaction);
+		}
+
+		let max_force = interactions.iter().map(Interaction::force).fold(0.0, Float::max);
+
+		Ok(Self { interactions, max_force })
+	}
+}
+
+// on error returns the message, the 1-based column and the bad token
+fn read_interaction(line: &str) -> Result<Interaction, (String, usize, &str)> {
+	let tokens: Vec<(usize, &str)> = line.split(',')
+		.map(|value| {
+			let token = value.trim();
+			(token.as_ptr() as usize - line.as_ptr() as usize + 1, token)
+		})
+		.filter(|(_, token)| !token.is_empty())
+		.collect();
+
+	if !matches!(tokens.len(), 3 | 4 | 5 | 8) {
+		let msg = format!("expected 3, 4, 5 or 8 values (2 ids and the forces), found {}", tokens.len());
+		return Err((msg, line.trim_end().len() + 1, ""));
+	}
+
+	let mut ids = [0; 2];
+	for (id, &(col, token)) in ids.iter_mut().zip(&tokens) {
+		*id = token.parse().map_err(|_| ("could not parse a body id".to_owned(), col, token))?;
+	}
+
+	let mut forces = Vec::with_capacity(tokens.len() - 2);
+	for &(col, token) in &tokens[2..] {
+		let force: Float = token.parse().map_err(|_| ("could not parse a force".to_owned(), col, token))?;
+		forces.push(force);
+	}
+
+	let magnitude = |values: &[Float]| values.iter().map(|value| value * value).sum::<Float>().sqrt();
+	let (normal_force, shear_force) = match forces.len() {
+		1 => (forces[0].abs(), 0.0),
+		2 => (forces[0].abs(), forces[1].abs()),
+		3 => (magnitude(&forces), 0.0),
+		_ => (magnitude(&forces[..3]), magnitude(&forces[3..])),
+	};
+
+	Ok(Interaction { id0: ids[0], id1: ids[1], normal_force, shear_force })
+}
@@ -0,0 +1,142 @@
+use std::{fs::{self, File}, io::{self, BufRead, BufReader}, sync::mpsc::{self, Receiver, Sender, TryRecvError}, thread};
+
+use crate::renderer::SceneUpdates;
+
+use super::{coordinate_convention::{CoordinateConvention, WorldTransform}, obj_reader::ReaderError, yade_dem_reader::YadeLineReader, yade_timeline::{YadeFrame, YadeTimeline}};
+
+
+// the path of stdin, "yade run.py | peekdem -"
+pub const STDIN_PATH: &str = "-";
+
+// stdin or a named pipe, they are read as frames arrive instead of all at once
+pub fn is_stream_source(path: &str) -> bool {
+	path == STDIN_PATH || is_fifo(path)
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &str) -> bool {
+	use std::os::unix::fs::FileTypeExt;
+	fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &str) -> bool {
+	false
+}
+
+// YADE frames written to a stream by a running simulation, read on another thread, a blank line or a "# frame" line ends a frame,
+// "# frame 1200" also sets the iteration of the next one, every frame replaces the one before it
+pub struct YadeStream {
+	receiver: Receiver<Result<YadeTimeline, ReaderError>>,
+	// the first line of the last bad frame, cleared by a good one
+	error: Option<String>,
+}
+
+impl YadeStream {
+	pub fn spawn(path: &str, convention: CoordinateConvention) -> Self {
+		let (sender, receiver) = mpsc::channel();
+
+		let path = path.to_owned();
+		thread::spawn(move || read_stream(&path, convention, &sender));
+
+		Self { receiver, error: None }
+	}
+}
+
+impl SceneUpdates<YadeTimeline> for YadeStream {
+	// the newest frame, the older ones that arrived since the last poll are skipped
+	fn poll(&mut self) -> Option<YadeTimeline> {
+		let mut newest = None;
+
+		loop {
+			match self.receiver.try_recv() {
+				Ok(Ok(timeline)) => {
+					self.error = None;
+					newest = Some(timeline);
+				}
+				Ok(Err(err)) => {
+					let err_str = err.to_string();
+					self.error = Some(err_str.lines().next().unwrap_or_default().to_owned());
+				}
+				// the stream ended, the last frame stays
+				Err(TryRecvError::Empty | TryRecvError::Disconnected) => return newest,
+			}
+		}
+	}
+
+	fn error(&self) -> Option<&str> {
+		self.error.as_deref()
+	}
+}
+
+// named pipes are opened again when their writer closes them, so the simulation can be restarted
+fn read_stream(path: &str, convention: CoordinateConvention, sender: &Sender<Result<YadeTimeline, ReaderError>>) {
+	// found from the first frame, so the next ones line up with it
+	let mut transform = None;
+
+	loop {
+		let keep_reading = if path == STDIN_PATH {
+			send_frames(io::stdin().lock(), "stdin", convention, &mut transform, sender)
+		} else {
+			match File::open(path) {
+				Ok(file) => send_frames(BufReader::new(file), path, convention, &mut transform, sender),
+				Err(err) => {
+					let _ = sender.send(Err(ReaderError::from_io_error(err, path)));
+					false
+				}
+			}
+		};
+
+		if !keep_reading || path == STDIN_PATH { return }
+	}
+}
+
+// false if the frames can't be sent anymore or the stream can't be read
+fn send_frames(stream: impl BufRead, path: &str, convention: CoordinateConvention, transform: &mut Option<WorldTransform>, sender: &Sender<Result<YadeTimeline, ReaderError>>) -> bool {
+	let mut reader = YadeLineReader::default();
+	let mut iteration = None;
+	// the rest of a bad frame is skipped
+	let mut frame_error = None;
+
+	let mut send_frame = |reader: &mut YadeLineReader, iteration: Option<u64>, frame_error: Option<ReaderError>| {
+		let data = reader.take_frame();
+		if let Some(err) = frame_error {
+			return sender.send(Err(err)).is_ok();
+		}
+
+		// blank lines one after the other
+		if data.bodies_count() == 0 { return true }
+
+		let frame = YadeFrame { path: path.to_owned(), iteration, data };
+		let transform = *transform.get_or_insert_with(|| WorldTransform::new(&convention, &frame.data));
+		sender.send(Ok(YadeTimeline::from_frames_with_transform(vec![frame], transform))).is_ok()
+	};
+
+	for (line_index, line) in stream.lines().enumerate() {
+		let line = match line {
+			Ok(line) => line,
+			Err(err) => {
+				let _ = sender.send(Err(ReaderError::from_io_error(err, path)));
+				return false;
+			}
+		};
+
+		let trimmed = line.trim();
+		let frame_marker = trimmed.strip_prefix('#').and_then(|comment| comment.trim().strip_prefix("frame"));
+		let is_frame_marker = frame_marker.is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace));
+
+		if trimmed.is_empty() || is_frame_marker {
+			if !send_frame(&mut reader, iteration, frame_error.take()) { return false }
+			iteration = frame_marker.filter(|_| is_frame_marker).and_then(|rest| rest.trim().parse().ok());
+			continue;
+		}
+
+		if frame_error.is_some() { continue }
+
+		if let Err((msg, col, token)) = reader.read_line(&line) {
+			frame_error = Some(ReaderError::BadToken { path: path.to_owned(), msg, line: line_index + 1, col, token: token.to_owned() });
+		}
+	}
+
+	send_frame(&mut reader, iteration, frame_error)
+}
@@ -0,0 +1,209 @@
+use std::{fs, path::Path};
+
+use super::{coordinate_convention::{CoordinateConvention, WorldTransform}, file_format::FileFormat, obj_reader::ReaderError, yade_dem_reader::YadeDemData};
+
+
+pub struct YadeFrame {
+	pub path: String,
+	// simulation iteration, taken from the last number in the file name
+	pub iteration: Option<u64>,
+	pub data: YadeDemData,
+}
+
+impl YadeFrame {
+	// the only snapshot of a file, it has no iteration
+	pub fn from_single_file(path: &str, data: YadeDemData) -> Self {
+		Self { path: path.to_owned(), iteration: None, data }
+	}
+}
+
+// snapshots of a DEM run, in order, in world coordinates
+pub struct YadeTimeline {
+	pub frames: Vec<YadeFrame>,
+	// from the file units, the same for every frame so they line up
+	pub transform: WorldTransform,
+}
+
+impl YadeTimeline {
+	// 'frames' are in file units, the transform is found from the first one
+	pub fn from_frames(frames: Vec<YadeFrame>, convention: &CoordinateConvention) -> Self {
+		let transform = WorldTransform::new(convention, &frames[0].data);
+		Self::from_frames_with_transform(frames, transform)
+	}
+
+	// 'frames' are in file units, 'transform' is kept from other frames so they line up with them
+	pub fn from_frames_with_transform(mut frames: Vec<YadeFrame>, transform: WorldTransform) -> Self {
+		for frame in frames.iter_mut() {
+			transform.apply(&mut frame.data);
+		}
+
+		Self { frames, transform }
+	}
+
+	// a directory, a glob ("out/snap_*.txt") or a numbered pattern ("out/snap_%05d.txt") instead of a single file
+	pub fn is_sequence_source(source: &str) -> bool {
+		Path::new(source).is_dir() || FramePattern::parse(source).is_some()
+	}
+
+	// the frames of anything 'is_sequence_source' accepts, in file units, each file is read with 'read_frame'
+	pub fn read_sequence_frames(source: &str, read_frame: impl Fn(&str) -> Result<YadeDemData, ReaderError>) -> Result<Vec<YadeFrame>, ReaderError> {
+		let paths = list_frame_paths(source, |path| matches!(FileFormat::detect(path), Ok(FileFormat::Yade)))?;
+		if paths.is_empty() {
+			return Err(ReaderError::FileNotFound(source.to_owned()));
+		}
+
+		let mut frames = Vec::with_capacity(paths.len());
+		for (path, iteration) in paths {
+			let data = read_frame(&path)?;
+			frames.push(YadeFrame { path, iteration, data });
+		}
+
+		Ok(frames)
+	}
+
+	pub fn len(&self) -> usize {
+		self.frames.len()
+	}
+}
+
+// paths of the frames with their iteration, sorted by iteration and then by name,
+// in a directory only the files 'is_frame_file' accepts are frames
+pub fn list_frame_paths(source: &str, is_frame_file: fn(&str) -> bool) -> Result<Vec<(String, Option<u64>)>, ReaderError> {
+
+	let source_path = Path::new(source);
+
+	// other files in a directory (logs, scripts) are skipped
+	let (dir, pattern) = if source_path.is_dir() {
+		(source_path, None)
+	} else {
+		let dir = source_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+		let file_name = source_path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+		(dir, FramePattern::parse(file_name))
+	};
+
+	let entries = fs::read_dir(dir).map_err(|err| ReaderError::from_io_error(err, &dir.to_string_lossy()))?;
+
+	let mut paths = vec![];
+	for entry in entries {
+		let entry = entry.map_err(ReaderError::IOError)?;
+		let path = entry.path();
+		if !path.is_file() { continue }
+
+		let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else { continue };
+		if file_name.starts_with('.') { continue }
+
+		let path_str = path.to_string_lossy().into_owned();
+
+		let iteration = match &pattern {
+			Some(pattern) => match pattern.match_iteration(file_name) {
+				Some(iteration) => iteration,
+				None => continue,
+			},
+			None => {
+				if !is_frame_file(&path_str) { continue }
+				last_number_in(file_name)
+			}
+		};
+
+		paths.push((path_str, iteration));
+	}
+
+	paths.sort_by(|(path_a, iteration_a), (path_b, iteration_b)| iteration_a.cmp(iteration_b).then_with(|| path_a.cmp(path_b)));
+
+	Ok(paths)
+}
+
+fn last_number_in(name: &str) -> Option<u64> {
+	let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+	let digits_end = stem.rfind(|ch: char| ch.is_ascii_digit())? + 1;
+	let digits_start = stem[..digits_end].rfind(|ch: char| !ch.is_ascii_digit()).map_or(0, |it| it + 1);
+
+	stem[digits_start..digits_end].parse().ok()
+}
+
+
+enum PatternPiece {
+	Literal(char),
+	// '*'
+	AnyChars,
+	// '?'
+	AnyChar,
+	// "%d", or "%05d" with a fixed width
+	Number(Option<usize>),
+}
+
+// wildcards are only supported in the file name, not in the directories
+struct FramePattern {
+	pieces: Vec<PatternPiece>,
+}
+
+impl FramePattern {
+	// None if there are no wildcards nor numbers in the name
+	fn parse(file_name: &str) -> Option<Self> {
+		let file_name = Path::new(file_name).file_name()?.to_str()?;
+
+		let mut pieces = vec![];
+		let mut chars = file_name.chars().peekable();
+
+		while let Some(ch) = chars.next() {
+			let piece = match ch {
+				'*' => PatternPiece::AnyChars,
+				'?' => PatternPiece::AnyChar,
+				'%' => {
+					let mut width_str = String::new();
+					while let Some(&digit) = chars.peek().filter(|ch| ch.is_ascii_digit()) {
+						width_str.push(digit);
+						chars.next();
+					}
+
+					if chars.peek() != Some(&'d') { return None }
+					chars.next();
+
+					PatternPiece::Number(width_str.parse().ok())
+				}
+				_ => PatternPiece::Literal(ch),
+			};
+			pieces.push(piece);
+		}
+
+		let has_wildcards = pieces.iter().any(|piece| !matches!(piece, PatternPiece::Literal(_)));
+		has_wildcards.then_some(Self { pieces })
+	}
+
+	// Some(iteration) if it matches, the iteration is the "%d" number or the last number in the name
+	fn match_iteration(&self, file_name: &str) -> Option<Option<u64>> {
+		let chars: Vec<char> = file_name.chars().collect();
+		let mut number = None;
+
+		if !Self::matches(&self.pieces, &chars, &mut number) { return None }
+
+		Some(number.or_else(|| last_number_in(file_name)))
+	}
+
+	fn matches(pieces: &[PatternPiece], chars: &[char], number: &mut Option<u64>) -> bool {
+		let Some((piece, rest)) = pieces.split_first() else { return chars.is_empty() };
+
+		match piece {
+			PatternPiece::Literal(literal) => chars.first() == Some(literal) && Self::matches(rest, &chars[1..], number),
+			PatternPiece::AnyChar => !chars.is_empty() && Self::matches(rest, &chars[1..], number),
+			PatternPiece::AnyChars => (0..=chars.len()).any(|skip| Self::matches(rest, &chars[skip..], number)),
+			PatternPiece::Number(width) => {
+				let digits_count = chars.iter().take_while(|ch| ch.is_ascii_digit()).count();
+				let widths = match width {
+					Some(width) if *width <= digits_count => *width..=*width,
+					Some(_) => return false,
+					None => 1..=digits_count,
+				};
+
+				// tries the longest numbers first
+				widths.rev().any(|width| {
+					let is_match = Self::matches(rest, &chars[width..], number);
+					if is_match {
+						*number = String::from_iter(&chars[..width]).parse().ok();
+					}
+					is_match
+				})
+			}
+		}
+	}
+}
@@ -0,0 +1,100 @@
+use std::{sync::{mpsc::{self, Receiver, TryRecvError}, Arc}, thread, time::{Duration, Instant}};
+
+use crate::{file_readers::{binary_cache::{file_stamp, FileStamp}, obj_reader::ReaderError}, renderer::SceneUpdates};
+
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// the data and the files it included
+type LoadResult<T> = Result<(T, Vec<String>), ReaderError>;
+type Loader<T> = Arc<dyn Fn() -> LoadResult<T> + Send + Sync>;
+
+// watches the input file and the files it includes (the material libraries of an OBJ),
+// and reads it again on another thread when their modification time or size change
+pub struct HotReload<T> {
+	path: String,
+	includes: Vec<String>,
+	load: Loader<T>,
+	// of the file and its includes when they were last read
+	last_stamps: Vec<FileStamp>,
+	last_poll: Instant,
+	// the read in progress
+	pending: Option<Receiver<LoadResult<T>>>,
+	// the first line of the last failed read, cleared by a read that works
+	error: Option<String>,
+}
+
+impl<T: Send + 'static> HotReload<T> {
+	// the file was just read with 'load', including 'includes'
+	pub fn new(path: &str, includes: Vec<String>, load: impl Fn() -> LoadResult<T> + Send + Sync + 'static) -> Self {
+		let last_stamps = Self::stamps(path, &includes);
+		Self {
+			path: path.to_owned(),
+			includes,
+			load: Arc::new(load),
+			last_stamps,
+			last_poll: Instant::now(),
+			pending: None,
+			error: None,
+		}
+	}
+
+	// the file first
+	fn stamps(path: &str, includes: &[String]) -> Vec<FileStamp> {
+		std::iter::once(path).chain(includes.iter().map(String::as_str)).map(file_stamp).collect()
+	}
+}
+
+impl<T: Send + 'static> SceneUpdates<T> for HotReload<T> {
+	// Some with the new data once a read started by a change is done
+	fn poll(&mut self) -> Option<T> {
+		if let Some(pending) = &self.pending {
+			let result = match pending.try_recv() {
+				Ok(result) => result,
+				Err(TryRecvError::Empty) => return None,
+				Err(TryRecvError::Disconnected) => Err(ReaderError::bad_format("the reload thread panicked".to_owned(), 1, 1)),
+			};
+			self.pending = None;
+
+			return match result {
+				Ok((data, includes)) => {
+					self.error = None;
+					// the file was edited to include others, they are watched from now on
+					if includes != self.includes {
+						self.last_stamps = Self::stamps(&self.path, &includes);
+						self.includes = includes;
+					}
+					Some(data)
+				}
+				Err(err) => {
+					let err_str = err.to_string();
+					self.error = Some(err_str.lines().next().unwrap_or_default().to_owned());
+					None
+				}
+			};
+		}
+
+		if self.last_poll.elapsed() < POLL_INTERVAL { return None }
+		self.last_poll = Instant::now();
+
+		// a file being rewritten can be missing for a moment, it's read when it comes back
+		let stamps = Self::stamps(&self.path, &self.includes);
+		if stamps[0].is_none() || stamps == self.last_stamps { return None }
+
+		// if it changes again while it's read, the next poll reads it again
+		self.last_stamps = stamps;
+
+		let (sender, receiver) = mpsc::channel();
+		let load = Arc::clone(&self.load);
+		thread::spawn(move || {
+			let _ = sender.send(load());
+		});
+		self.pending = Some(receiver);
+
+		None
+	}
+
+	fn error(&self) -> Option<&str> {
+		self.error.as_deref()
+	}
+}
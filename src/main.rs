@@ -23,39 +23,250 @@ mod fps_measure;
 mod benchmark;
 mod settings;
 mod utils;
+mod hot_reload;
+mod scene_server;
+mod scene_client;
 
 
-use std::{env, io};
+use std::{env, fs, io};
 
 use app::App;
-use file_readers::yade_dem_reader::YadeDemData;
-use rendering::{camera::Camera, mesh::Mesh, renderer::Renderer, yade_renderer::YadeRenderer, *};
+use file_readers::{binary_cache::{cache_path_for, read_cache_file, read_cache_file_with_header, read_with_cache, write_cache_file, file_stamp, CacheHeader, CachedScene}, loading_progress::disable_progress_reports, lammps_dump_reader::read_frames_from_lammps_dump, sphere_list_reader::read_spheres_from_list, vtk_reader::read_from_vtk_file, yade_dem_reader::{BadLinesMode, YadeDemData}, yade_interactions::YadeInteractions, yade_stream::{is_stream_source, YadeStream}, yade_timeline::{YadeFrame, YadeTimeline}};
+use rendering::{camera::Camera, mesh::Mesh, renderer::{Renderer, SceneUpdates}, yade_renderer::YadeRenderer, *};
 use settings::Settings;
+use hot_reload::HotReload;
+use scene_client::SceneClient;
+use scene_server::{ListenAddress, SceneServer};
 use timer::Timer;
 use fps_measure::FpsMeasure;
 use terminal::*;
 use maths::*;
 
-use crate::{benchmark::Benchmark, file_readers::obj_reader::read_mesh_from_obj_file, obj_renderer::ObjRenderer};
+use crate::{benchmark::Benchmark, file_readers::{file_format::FileFormat, obj_reader::ReaderError}, obj_renderer::ObjRenderer};
 
 
 fn main() {
 	let args = env::args().skip(1);
 	let settings = Settings::from_args(args);
 
-	if !settings.has_custom_path {
+	// a viewer that listens can start without bodies
+	let can_start_empty = settings.listen.is_some() && !settings.convert && settings.send_address.is_none();
+	if !settings.has_custom_path && !can_start_empty {
 		eprintln!("Provide a path");
 		std::process::exit(1);
 	}
 
-	if settings.custom_path.ends_with(".obj") {
-		// TODO: "or_quit" function
-		let obj_renderer = ObjRenderer::new(read_mesh_from_obj_file(&settings.custom_path).unwrap());
-		run_pipeline(obj_renderer);
-	} else {
-		let yade_dem_renderer = YadeRenderer::new(YadeDemData::read_from_file_or_quit(&settings.custom_path));
-		run_pipeline(yade_dem_renderer);
+	let path = &settings.custom_path;
+
+	if let Some(address) = &settings.send_address {
+		send_to_viewer(address, path);
+		return;
+	}
+
+	// the bodies are pushed by clients
+	if let (Some(address), false) = (&settings.listen, settings.has_custom_path) {
+		let empty_frame = YadeFrame::from_single_file(&address.to_string(), YadeDemData::empty());
+		let timeline = YadeTimeline::from_frames(vec![empty_frame], &settings.coordinate_convention);
+		let server = listen(address, YadeDemData::empty(), &timeline, &settings);
+
+		run_yade_pipeline(timeline, &settings, Some(server));
+		return;
+	}
+
+	// frames are drawn as they arrive, the first one is empty
+	if is_stream_source(path) {
+		if settings.convert {
+			eprintln!("convert takes a file, not stdin or a named pipe");
+			std::process::exit(1);
+		}
+
+		if settings.listen.is_some() {
+			eprintln!("--listen takes a file, not stdin or a named pipe");
+			std::process::exit(1);
+		}
+
+		let empty_frame = YadeFrame::from_single_file(path, YadeDemData::empty());
+		let timeline = YadeTimeline::from_frames(vec![empty_frame], &settings.coordinate_convention);
+		let stream = YadeStream::spawn(path, settings.coordinate_convention);
+
+		run_yade_pipeline(timeline, &settings, Some(Box::new(stream)));
+		return;
+	}
+
+	let format = match settings.format {
+		Some(format) => format,
+		None if YadeTimeline::is_sequence_source(path) => FileFormat::Yade,
+		None => FileFormat::detect(path).unwrap_or_else(|err| quit_with_reader_error(&err, path)),
+	};
+
+	if settings.convert {
+		convert_to_cache(path, format, &settings);
+		return;
+	}
+
+	let scene = read_scene(path, format, &settings).unwrap_or_else(|err| quit_with_reader_error(&err, path));
+
+	match scene {
+		CachedScene::Mesh(mesh) => {
+			if settings.listen.is_some() {
+				eprintln!("--listen shows DEM bodies, '{}' is a mesh", path);
+				std::process::exit(1);
+			}
+
+			let hot_reload = watch_input(path, format, &settings, mesh.mtl_paths.clone(), |scene, _| match scene {
+				CachedScene::Mesh(mesh) => Ok(ObjRenderer::prepare_mesh(*mesh)),
+				CachedScene::Frames(_) => Err(ReaderError::bad_format("the file is not a mesh anymore".to_owned(), 1, 1)),
+			});
+
+			run_pipeline(ObjRenderer::new(*mesh), &settings, hot_reload);
+		}
+		CachedScene::Frames(frames) => {
+			// clients change the first frame, in file units
+			let first_frame_data = settings.listen.as_ref().map(|_| frames[0].data.clone());
+			let timeline = YadeTimeline::from_frames(frames, &settings.coordinate_convention);
+
+			let scene_updates = match (&settings.listen, first_frame_data) {
+				(Some(address), Some(data)) => Some(listen(address, data, &timeline, &settings)),
+				_ => watch_input(path, format, &settings, vec![], |scene, settings| match scene {
+					CachedScene::Frames(frames) => Ok(YadeTimeline::from_frames(frames, &settings.coordinate_convention)),
+					CachedScene::Mesh(_) => Err(ReaderError::bad_format("the file is a mesh now, not DEM snapshots".to_owned(), 1, 1)),
+				}),
+			};
+
+			run_yade_pipeline(timeline, &settings, scene_updates);
+		}
+	}
+}
+
+fn run_yade_pipeline(timeline: YadeTimeline, settings: &Settings, scene_updates: Option<Box<dyn SceneUpdates<YadeTimeline>>>) {
+	let interactions = match &settings.interactions_path {
+		Some(interactions_path) => YadeInteractions::read_for_timeline(interactions_path, &timeline).unwrap_or_else(|err| quit_with_reader_error(&err, interactions_path)),
+		None => vec![],
+	};
+
+	let mut yade_dem_renderer = YadeRenderer::new(timeline);
+	yade_dem_renderer.set_interactions(interactions, settings.force_thresholds);
+
+	run_pipeline(yade_dem_renderer, settings, scene_updates);
+}
+
+// 'data' is the first frame of 'timeline' in file units, clients change it
+fn listen(address: &ListenAddress, data: YadeDemData, timeline: &YadeTimeline, settings: &Settings) -> Box<dyn SceneUpdates<YadeTimeline>> {
+	match SceneServer::listen(address, data, timeline.transform, settings.coordinate_convention) {
+		Ok(server) => Box::new(server),
+		Err(err) => {
+			eprintln!("Can't listen on '{}'", address);
+			eprintln!("{}", err);
+			std::process::exit(1);
+		}
+	}
+}
+
+// "peekdem send <address> <file>", the lines of the file are sent as they are, the viewer reads them as a YADE snapshot
+fn send_to_viewer(address: &ListenAddress, path: &str) {
+	let text = fs::read_to_string(path).unwrap_or_else(|err| quit_with_reader_error(&ReaderError::from_io_error(err, path), path));
+
+	let sent = SceneClient::connect(address).and_then(|mut client| client.send_frame_lines(None, text.lines()));
+	if let Err(err) = sent {
+		eprintln!("Error sending '{}' to '{}'", path, address);
+		eprintln!("{}", err);
+		std::process::exit(1);
+	}
+
+	println!("Sent '{}' to '{}'", path, address);
+}
+
+// reads the input again in the background when it or the files it includes change on disk,
+// 'to_scene' turns it into what the renderer draws, sequences are not watched
+fn watch_input<T: Send + 'static>(path: &str, format: FileFormat, settings: &Settings, includes: Vec<String>, to_scene: fn(CachedScene, &Settings) -> Result<T, ReaderError>) -> Option<Box<dyn SceneUpdates<T>>> {
+	if !settings.hot_reload || YadeTimeline::is_sequence_source(path) { return None }
+
+	let path_owned = path.to_owned();
+	let settings = settings.clone();
+	Some(Box::new(HotReload::new(path, includes, move || {
+		let scene = read_scene(&path_owned, format, &settings)?;
+		let includes = scene.includes();
+		to_scene(scene, &settings).map(|data| (data, includes))
+	})))
+}
+
+// the file, or every file of a YADE sequence, in file units
+fn read_scene(path: &str, format: FileFormat, settings: &Settings) -> Result<CachedScene, ReaderError> {
+	if format != FileFormat::Yade || !YadeTimeline::is_sequence_source(path) {
+		return read_file(path, format, settings);
+	}
+
+	let frames = YadeTimeline::read_sequence_frames(path, |frame_path| match read_file(frame_path, format, settings)? {
+		CachedScene::Frames(mut frames) => Ok(frames.swap_remove(0).data),
+		CachedScene::Mesh(_) => Err(ReaderError::bad_format(format!("the cache of '{}' is a mesh, not a YADE snapshot", frame_path), 1, 1)),
+	})?;
+
+	Ok(CachedScene::Frames(frames))
+}
+
+// from its binary cache if the file didn't change since it was written, else the cache is written after parsing it
+fn read_file(path: &str, format: FileFormat, settings: &Settings) -> Result<CachedScene, ReaderError> {
+	let parse = || parse_file(path, format, settings);
+
+	if !settings.use_cache || format == FileFormat::Cache {
+		return parse();
+	}
+
+	read_with_cache(path, &cache_key(format, settings), parse)
+}
+
+fn parse_file(path: &str, format: FileFormat, settings: &Settings) -> Result<CachedScene, ReaderError> {
+	if let Some(read_mesh) = format.mesh_reader() {
+		return read_mesh(path).map(|mesh| CachedScene::Mesh(Box::new(mesh)));
+	}
+
+	let single_frame = |data| CachedScene::Frames(vec![YadeFrame::from_single_file(path, data)]);
+
+	match format {
+		FileFormat::Lammps => read_frames_from_lammps_dump(path).map(CachedScene::Frames),
+		FileFormat::Spheres => read_spheres_from_list(path, &settings.sphere_list).map(single_frame),
+		FileFormat::Vtk => read_from_vtk_file(path).map(single_frame),
+		FileFormat::Cache => read_cache_file(path),
+		_ if settings.report_all_errors => YadeDemData::read_from_file_with(path, BadLinesMode::CollectAll).map(single_frame),
+		_ => YadeDemData::read_from_file(path).map(single_frame),
+	}
+}
+
+// what changes how a file is parsed, caches written with other options are not used
+fn cache_key(format: FileFormat, settings: &Settings) -> String {
+	match format {
+		FileFormat::Spheres => format!("{} {:?}", format.name(), settings.sphere_list),
+		_ => format.name().to_owned(),
+	}
+}
+
+// "peekdem convert <input> [<output>]", the cache is written next to the input by default
+fn convert_to_cache(path: &str, format: FileFormat, settings: &Settings) {
+	if YadeTimeline::is_sequence_source(path) {
+		eprintln!("convert takes a single file, not a sequence");
+		std::process::exit(1);
+	}
+
+	// a cache is copied with the header of its source
+	let read_result = match format {
+		FileFormat::Cache => read_cache_file_with_header(path),
+		_ => {
+			let source_stamp = file_stamp(path);
+			parse_file(path, format, settings).map(|scene| (CacheHeader::new(&cache_key(format, settings), path, source_stamp, &scene), scene))
+		}
 	};
+	let (header, scene) = read_result.unwrap_or_else(|err| quit_with_reader_error(&err, path));
+
+	let output = settings.convert_output.clone().unwrap_or_else(|| cache_path_for(path));
+	write_cache_file(&output, &header, &scene).unwrap_or_else(|err| quit_with_reader_error(&err, &output));
+
+	println!("Wrote '{}'", output);
+}
+
+fn quit_with_reader_error(err: &ReaderError, path: &str) -> ! {
+	eprintln!("Error reading '{}'", path);
+	eprintln!("{}", err);
+	std::process::exit(1);
 }
 
 // TODO: try functional with this less blurry crap
@@ -64,8 +275,12 @@ fn main() {
 // type RenderYadeFn = fn(&YadeDemData, &mut TerminalBuffer, &Timer, &Camera);
 
 
-fn run_pipeline<T: Renderer>(renderer: T) {
+fn run_pipeline<T: Renderer>(mut renderer: T, settings: &Settings, mut scene_updates: Option<Box<dyn SceneUpdates<T::Scene>>>) {
 	let mut app = App::init_with_screen();
+	app.buf.use_color = settings.use_color;
+	if settings.draw_wireframe {
+		app.buf.toggle_mesh_fill_mode();
+	}
 	// let mut app = App::init_wh(80 * 3 / 2, 30 * 3 / 2);
 
 	let mut timer = Timer::new();
@@ -80,8 +295,15 @@ fn run_pipeline<T: Renderer>(renderer: T) {
 
 	let mut terminal = configure_terminal();
 	set_panic_hook();
+	disable_progress_reports();
 
-	let print_to_terminal_func = if app.is_full_screen { print_and_flush_terminal_fscreen } else { print_and_flush_terminal_line_by_line };
+	let print_to_terminal_func = if app.buf.use_color {
+		print_and_flush_terminal_color
+	} else if app.is_full_screen {
+		print_and_flush_terminal_fscreen
+	} else {
+		print_and_flush_terminal_line_by_line
+	};
 
 	// let yade_debug = YadeDemData::debug();
 	// let mesh_debug = Mesh::pillars();
@@ -103,7 +325,20 @@ fn run_pipeline<T: Renderer>(renderer: T) {
 		poll_events(&mut terminal, &mut app, &mut timer);
 		bench!(b, "poll events", &mut app.buf);
 
+		if let Some(scene_updates) = scene_updates.as_mut() {
+			// the camera and the render modes are kept
+			if let Some(scene) = scene_updates.poll() {
+				renderer.set_scene(scene);
+			}
+
+			for view_command in scene_updates.take_view_commands() {
+				view_command.apply(&mut camera, &mut app);
+			}
+		}
+
 		camera.consume_user_data(&mut app);
+		renderer.consume_user_data(&mut app);
+		renderer.update(&timer);
 
 		bench_st!(b);
 
@@ -123,7 +358,10 @@ fn run_pipeline<T: Renderer>(renderer: T) {
 		// bench!(b, "renderer test", &mut app.buf);
 
 		fps_measure.profile_frame(&timer);
-		render_verbose(&fps_measure, &camera, &mut app);
+		render_verbose(&fps_measure, &camera, &renderer, &mut app);
+		if let Some(error) = scene_updates.as_ref().and_then(|scene_updates| scene_updates.error()) {
+			render_reload_error(error, &mut app.buf);
+		}
 		bench!(b, "render verbose", &mut app.buf);
 
 		timer.run_frame();
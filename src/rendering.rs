@@ -23,9 +23,9 @@ pub use ball_painter::*;
 
 use help_screen::HELP_SCR;
 
-use crate::{app::App, camera::Camera, fps_measure::FpsMeasure, maths::*, terminal::TerminalBuffer, timer::Timer, utils::*};
+use crate::{app::App, camera::Camera, fps_measure::FpsMeasure, maths::*, terminal::{TerminalBuffer, NO_COLOR}, timer::Timer, utils::*};
 
-use self::cull_mode::CullMode;
+use self::{cull_mode::CullMode, renderer::Renderer};
 
 
 // ascii luminance:
@@ -50,6 +50,8 @@ pub fn render_clear(buffer: &mut TerminalBuffer) {
 	debug_assert!(BACKGROUND_FILL_CHAR.len_utf8() == 1, "Background fill should be ASCII");
 
 	buffer.raw_ascii_screen.fill(BACKGROUND_FILL_CHAR as u8);
+	buffer.raw_color_screen.fill(NO_COLOR);
+	buffer.depth_screen.fill(f32::INFINITY);
 
 
 	// only needs to care about this for braille rendering
@@ -69,7 +71,7 @@ pub fn render_clear(buffer: &mut TerminalBuffer) {
 }
 
 
-pub fn render_verbose(fps_measure: &FpsMeasure, camera: &Camera, app: &mut App) {
+pub fn render_verbose(fps_measure: &FpsMeasure, camera: &Camera, renderer: &impl Renderer, app: &mut App) {
 
 	const PAUSED_STR: &str = " ENGINE RUNNING! ";
 	render_string_snap_right(PAUSED_STR, &UVec2::new(0, app.buf.hei - 1), &mut app.buf);
@@ -81,10 +83,12 @@ pub fn render_verbose(fps_measure: &FpsMeasure, camera: &Camera, app: &mut App)
 
 	let mut highest_pos = UVec2::new(0, 0);
 
+	// in the units of the file
+	let cam_pos = renderer.world_to_file_units(&camera.position);
 	#[cfg(debug_assertions)]
-	render_string(&format!("cam pos: {:?} ", camera.position), &highest_pos, buf);
+	render_string(&format!("cam pos: {:?} ", cam_pos), &highest_pos, buf);
 	#[cfg(not(debug_assertions))]
-	render_string(&format!("cam pos: {:} ", camera.position), &highest_pos, buf);
+	render_string(&format!("cam pos: {:} ", cam_pos), &highest_pos, buf);
 
 	highest_pos.y += 1;
 	#[cfg(debug_assertions)]
@@ -128,6 +132,12 @@ pub fn render_verbose(fps_measure: &FpsMeasure, camera: &Camera, app: &mut App)
 	render_string_snap_right(&format!(" move mode: {:} ", if is_free_mov { "free movement" } else { "orbital" }), &lowest_pos_br, buf);
 	lowest_pos_br.y -= 1;
 	render_string_snap_right(&format!(" light mode: {:} ", buf.get_ball_fill_mode()), &lowest_pos_br, buf);
+	lowest_pos_br.y -= 1;
+	render_string_snap_right(&format!(" ball shading: {:} ", buf.get_ball_shading_mode()), &lowest_pos_br, buf);
+	lowest_pos_br.y -= 1;
+	render_string_snap_right(&format!(" mesh mode: {:} ", buf.get_mesh_fill_mode()), &lowest_pos_br, buf);
+	lowest_pos_br.y -= 1;
+	render_string_snap_right(&format!(" mesh shading: {:} ", buf.get_mesh_shading_mode()), &lowest_pos_br, buf);
 
 	let gizmos_mode = buf.get_gizmos_mode();
 	if let GizmosType::WorldAxes = gizmos_mode {
@@ -135,6 +145,14 @@ pub fn render_verbose(fps_measure: &FpsMeasure, camera: &Camera, app: &mut App)
 		render_string_snap_right(&format!(" gizmos: {:} ", gizmos_mode), &lowest_pos_br, buf);
 	}
 
+	for line in renderer.verbose_lines() {
+		let line = format!(" {} ", line);
+		if lowest_pos_br.y <= 1 || line.len() >= buf.wid.into() || !line.is_ascii() { break }
+
+		lowest_pos_br.y -= 1;
+		render_string_snap_right(&line, &lowest_pos_br, buf);
+	}
+
 	let help_txt = " PRESS H FOR HELP ";
 	let center = UVec2::new(buf.wid / 2 - help_txt.len() as u16 / 2, 0);
 	render_string(&help_txt, &center, buf);
@@ -197,6 +215,15 @@ pub fn render_help_screen(buf: &mut TerminalBuffer) {
 }
 
 
+// under the help text, the error is cut to fit the line
+pub fn render_reload_error(error: &str, buf: &mut TerminalBuffer) {
+	let line: String = format!(" reload failed: {} ", error).chars().map(|ch| if ch.is_ascii_graphic() || ch == ' ' { ch } else { '?' }).collect();
+	let line = &line[..line.len().min(buf.wid as usize - 1)];
+
+	let center = UVec2::new(buf.wid / 2 - line.len() as u16 / 2, 1);
+	render_string(line, &center, buf);
+}
+
 pub fn render_string_snap_right(string: &str, pos: &UVec2, buf: &mut TerminalBuffer) {
 	let new_pos = UVec2::new(buf.wid - string.len() as u16 - pos.x, pos.y);
 	render_string(string, &new_pos, buf);
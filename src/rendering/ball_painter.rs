@@ -10,19 +10,54 @@ pub struct BallPainter {
 	pub range_dist_xz: f32,
 	pub range_height: f32,
 
+	pub min_attribute: f32,
+	pub max_attribute: f32,
+	pub range_attribute: f32,
+
 	paint_algorithm: fn(&BallPainter, &RenderBallData) -> char,
+	// 0 to 1, the same order as the chars of 'paint_algorithm'
+	tint_algorithm: fn(&BallPainter, &RenderBallData) -> f32,
 }
 
-const ASCII_LUMINANCE: &'static [char] = &[ '.', ',', '-', '~', ':', ';', '=', '!', '&', '#', '@', ];
+pub const ASCII_LUMINANCE: &'static [char] = &[ '.', ',', '-', '~', ':', ';', '=', '!', '&', '#', '@', ];
 	// = [ '.', '-', ':', '=', '!', '&', '#', '@' ];
 	// = [ '.', '-', ':', '=', '!', '&', '@' ];
 
+// the luminance of surfaces turned away from the light, so they are still drawn
+pub const AMBIENT_LIGHT: f32 = 0.1;
+
+// shaded spheres are tinted down to this by the fill mode, so the darkest ones still look round
+const MIN_SPHERE_TINT: f32 = 0.35;
+// shaded spheres lit by index cycle through this many tints
+const INDEX_TINT_LEVELS: usize = 4;
+
+// 0 is the darkest char, 1 the brightest
+pub fn luminance_to_char(luminance: f32) -> char {
+	let index = ( luminance.clamp(0.0, 1.0) * (ASCII_LUMINANCE.len() - 1) as f32 ).round() as usize;
+	ASCII_LUMINANCE[index]
+}
+
+// Lambert's cosine law, 0 to 1, surfaces turned away from the light get the ambient light
+pub fn lambert(normal: &Vec3, light_dir: &Vec3) -> f32 {
+	// normals read from files are not always normalized
+	let diffuse = Vec3::dot_product(&normal.normalized(), light_dir).max(0.0);
+	AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * diffuse
+}
+
 impl BallPainter {
 	pub fn new(fill_mode: &BallFillMode) -> Self {
 		let paint_algorithm = match fill_mode {
 			BallFillMode::Height     => Self::paint_by_height,
 			BallFillMode::XZDistance => Self::paint_by_xz_dist,
 			BallFillMode::Index      => Self::paint_by_index,
+			BallFillMode::Attribute  => Self::paint_by_attribute,
+		};
+
+		let tint_algorithm = match fill_mode {
+			BallFillMode::Height     => Self::tint_by_height,
+			BallFillMode::XZDistance => Self::tint_by_xz_dist,
+			BallFillMode::Index      => Self::tint_by_index,
+			BallFillMode::Attribute  => Self::tint_by_attribute,
 		};
 
 		Self {
@@ -33,11 +68,16 @@ impl BallPainter {
 			min_dist_xz_sq:   f32::MAX,
 			max_dist_xz_sq:   f32::MIN,
 			range_dist_xz: 0.0,
+
+			min_attribute:   f32::MAX,
+			max_attribute:   f32::MIN,
+			range_attribute: 0.0,
 			paint_algorithm,
+			tint_algorithm,
 		}
 	}
 
-	pub fn find_min_max(&mut self, transformed_pos: &Vec3, sq_dist_xz: f32) {
+	pub fn find_min_max(&mut self, transformed_pos: &Vec3, sq_dist_xz: f32, attribute: f32) {
 		self.min_dist_xz_sq = self.min_dist_xz_sq.min(sq_dist_xz);
 		self.max_dist_xz_sq = self.max_dist_xz_sq.max(sq_dist_xz);
 		self.range_dist_xz = self.max_dist_xz_sq - self.min_dist_xz_sq;
@@ -45,12 +85,25 @@ impl BallPainter {
 		self.min_height = self.min_height.min(transformed_pos.y);
 		self.max_height = self.max_height.max(transformed_pos.y);
 		self.range_height = self.max_height - self.min_height;
+
+		self.min_attribute = self.min_attribute.min(attribute);
+		self.max_attribute = self.max_attribute.max(attribute);
+		self.range_attribute = self.max_attribute - self.min_attribute;
 	}
 
 	pub fn get_fill_letter(&self, ball_data: &RenderBallData) -> char {
 		(self.paint_algorithm)(&self, ball_data)
 	}
 
+	// how much a shaded sphere is lit, MIN_SPHERE_TINT to 1
+	pub fn get_sphere_tint(&self, ball_data: &RenderBallData) -> f32 {
+		let tint = (self.tint_algorithm)(self, ball_data);
+
+		// a single ball has no range
+		let tint = if tint.is_nan() { 1.0 } else { tint.clamp(0.0, 1.0) };
+		MIN_SPHERE_TINT + (1.0 - MIN_SPHERE_TINT) * tint
+	}
+
 	fn paint_by_index(&self, ball_data: &RenderBallData) -> char {
 		let digit = ball_data.index as u32 % ('Z' as u32 - 'A' as u32) + ('A' as u32);
 		char::from_u32(digit).unwrap()
@@ -71,4 +124,32 @@ impl BallPainter {
 		ASCII_LUMINANCE[index]
 	}
 
+	// the highest values are the brightest, all balls are the same if they have the same value
+	fn paint_by_attribute(&self, ball_data: &RenderBallData) -> char {
+		if self.range_attribute <= 0.0 { return luminance_to_char(0.5) }
+
+		luminance_to_char((ball_data.attribute - self.min_attribute) / self.range_attribute)
+	}
+
+	// neighbouring balls get different levels, like the letters of 'paint_by_index'
+	fn tint_by_index(&self, ball_data: &RenderBallData) -> f32 {
+		(ball_data.index % INDEX_TINT_LEVELS) as f32 / (INDEX_TINT_LEVELS - 1) as f32
+	}
+
+	// the closest are the brightest, like 'paint_by_xz_dist'
+	fn tint_by_xz_dist(&self, ball_data: &RenderBallData) -> f32 {
+		1.0 - (ball_data.sq_dist_to_camera - self.min_dist_xz_sq) / self.range_dist_xz
+	}
+
+	// the lowest are the brightest, like 'paint_by_height'
+	fn tint_by_height(&self, ball_data: &RenderBallData) -> f32 {
+		1.0 - (ball_data.height - self.min_height) / self.range_height
+	}
+
+	fn tint_by_attribute(&self, ball_data: &RenderBallData) -> f32 {
+		if self.range_attribute <= 0.0 { return 0.5 }
+
+		(ball_data.attribute - self.min_attribute) / self.range_attribute
+	}
+
 }
\ No newline at end of file
@@ -22,6 +22,9 @@ pub struct Camera {
 }
 
 impl Camera {
+	// from the center of the orbit, before the user zooms
+	const ORBIT_DIST: f32 = 16.0;
+
 	pub fn new() -> Camera {
 		Self {
 			position: Vec3::zero(),
@@ -84,7 +87,7 @@ impl Camera {
 
 	fn solve_orbital_movement(camera: &mut Camera, app: &mut App) {
 
-		const BASE_POS: Vec3 = Vec3::new(0.0, 0.0, 16.0);
+		const BASE_POS: Vec3 = Vec3::new(0.0, 0.0, Camera::ORBIT_DIST);
 		const TOLERANCE: f32 = 1.0;
 
 		let ang_increment_x = app.user_dir.y;
@@ -131,6 +134,16 @@ impl Camera {
 		self.rotation.y = yaw + TAU /4.0;
 	}
 
+	// the orbital mode angles in radians, 'dist' is from the center of the orbit, it's clamped like the user's zoom
+	pub fn set_orbit(&mut self, rot_x: f32, rot_y: f32, dist: Option<f32>) {
+		self.cache_rot_x = rot_x;
+		self.cache_rot_y = rot_y;
+
+		if let Some(dist) = dist {
+			self.cache_dist = dist - Self::ORBIT_DIST;
+		}
+	}
+
 	pub fn reset_cached_dist(&mut self) {
 		self.cache_dist = 0.0;
 	}
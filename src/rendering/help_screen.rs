@@ -23,12 +23,31 @@ C: change culling mode, can cull balls, triangles
 Z: change Z-sorting mode, can render all triangles
 		after all of the spheres and vice-versa
 L: change spheres lighting mode, can be by index,
-		by camera distance or by height
+		by camera distance, by height or by attribute
+U: toggles spheres between shaded, tinted by the
+		lighting mode, and flat discs of a single char
+B: selects the next attribute (a YADE header column,
+		like velocity) to light spheres by
+F: raises the minimum force of the YADE contacts
+		drawn (--interactions), SHIFT + F lowers it
+
+K: toggles meshes between solid, hiding what's
+		behind them, and wireframe
+J: toggles the lighting of solid meshes between flat
+		(by face) and gouraud (blended from the vertices)
 
 SHIFT + C / L / Z: the same but in reverse order
 
 P: pauses / unpauses the engine, useful to copy
 		parts of the screen in some terminals
+SHIFT + P: pauses / unpauses the animation, like
+		the playback of YADE snapshots
+
+. / ,: steps YADE snapshots forwards / backwards
+
+N: selects the next mesh part (OBJ object / group),
+		highlighting it, SHIFT + N selects the previous
+X: hides / shows the selected mesh part
 
 G: toggles rendering of the XYZ world axis
 		(renders after everything else)
@@ -1,15 +1,79 @@
-use crate::{maths::Vec3, xy_to_it};
+use std::{collections::HashMap, ops::Range};
+
+use crate::maths::Vec3;
+
+// without smoothing groups, faces only share vertex normals if the angle between them is smaller than this
+pub const DEFAULT_CREASE_ANGLE_DEG: f32 = 60.0;
 
 pub struct Mesh {
 	pub verts: Vec<f32>,
 	// indices are 3 by 3, counterclockwise
-	pub tris_indices: Vec<u16>,
+	pub tris_indices: Vec<u32>,
 	pub normals: Vec<f32>,
 	// indices are 3 by 3, counterclockwise
-	pub normal_indices: Vec<u16>,
+	pub normal_indices: Vec<u32>,
+	// named objects / groups, each one is a range of triangles (not indices)
+	pub parts: Vec<MeshPart>,
+	pub materials: Vec<Material>,
+	// material index of each triangle, empty if the mesh has no materials
+	pub tri_materials: Vec<Option<u32>>,
+	// one normal per triangle, 3 floats each
+	pub face_normals: Vec<f32>,
+	// OBJ 's' of each triangle (0 is off), empty if the mesh has no smoothing groups
+	pub tri_smoothing_groups: Vec<u32>,
+	// RGB of each vertex, 0 to 1, 3 floats each, empty if the mesh has no vertex colors
+	pub vert_colors: Vec<f32>,
+	// the material libraries of an OBJ, found or not, so a cache or a reload notices when they change
+	pub mtl_paths: Vec<String>,
+}
+
+pub struct MeshPart {
+	pub name: String,
+	pub tris: Range<usize>,
+}
+
+pub struct Material {
+	pub name: String,
+	// RGB, 0 to 1
+	pub diffuse: Vec3,
+}
+
+impl Material {
+	// MTL default diffuse color
+	pub fn with_name(name: String) -> Self {
+		Self { name, diffuse: Vec3::new(0.8, 0.8, 0.8) }
+	}
+}
+
+// relative luminance, 0 to 1
+pub fn rgb_luminance(rgb: &Vec3) -> f32 {
+	(0.2126 * rgb.x + 0.7152 * rgb.y + 0.0722 * rgb.z).clamp(0.0, 1.0)
+}
+
+// closest color in the 6x6x6 cube of the 256 colors ANSI palette (16 to 231)
+pub fn rgb_to_ansi_color(rgb: &Vec3) -> u8 {
+	let to_cube = |channel: f32| (channel.clamp(0.0, 1.0) * 5.0).round() as u8;
+	16 + 36 * to_cube(rgb.x) + 6 * to_cube(rgb.y) + to_cube(rgb.z)
 }
 
 impl Mesh {
+	// everything but the vertices and their indices is empty
+	pub fn from_verts_and_indices(verts: Vec<f32>, tris_indices: Vec<u32>) -> Self {
+		Self {
+			verts,
+			tris_indices,
+			normals: vec! [],
+			normal_indices: vec! [],
+			parts: vec! [],
+			materials: vec! [],
+			tri_materials: vec! [],
+			face_normals: vec! [],
+			tri_smoothing_groups: vec! [],
+			vert_colors: vec! [],
+			mtl_paths: vec! [],
+		}
+	}
+
 	pub fn cube() -> Self {
 		Self {
 			verts: vec![
@@ -38,6 +102,13 @@ impl Mesh {
 			],
 			normals: vec! [],
 			normal_indices: vec! [],
+			parts: vec! [],
+			materials: vec! [],
+			tri_materials: vec! [],
+			face_normals: vec! [],
+			tri_smoothing_groups: vec! [],
+			vert_colors: vec! [],
+			mtl_paths: vec! [],
 		}
 	}
 
@@ -87,6 +158,13 @@ impl Mesh {
 			],
 			normals: vec! [],
 			normal_indices: vec! [],
+			parts: vec! [],
+			materials: vec! [],
+			tri_materials: vec! [],
+			face_normals: vec! [],
+			tri_smoothing_groups: vec! [],
+			vert_colors: vec! [],
+			mtl_paths: vec! [],
 		}
 	}
 
@@ -104,31 +182,172 @@ impl Mesh {
 			],
 			normals: vec! [],
 			normal_indices: vec! [],
+			parts: vec! [],
+			materials: vec! [],
+			tri_materials: vec! [],
+			face_normals: vec! [],
+			tri_smoothing_groups: vec! [],
+			vert_colors: vec! [],
+			mtl_paths: vec! [],
 		}
 	}
 
+	pub fn tris_count(&self) -> usize {
+		self.tris_indices.len() / 3
+	}
+
+	pub fn get_material_at(&self, tri_index: usize) -> Option<&Material> {
+		let material_index = (*self.tri_materials.get(tri_index)?)?;
+		self.materials.get(material_index as usize)
+	}
+
+	// the material's diffuse color, or the average of the triangle's vertex colors
+	pub fn get_color_at(&self, tri_index: usize) -> Option<Vec3> {
+		if let Some(material) = self.get_material_at(tri_index) {
+			return Some(material.diffuse);
+		}
+
+		if self.vert_colors.is_empty() { return None }
+
+		let mut color_sum = Vec3::zero();
+		for index_i in tri_index * 3 .. tri_index * 3 + 3 {
+			let it = self.tris_indices[index_i] as usize * 3;
+			color_sum = color_sum + Vec3::new(self.vert_colors[it], self.vert_colors[it + 1], self.vert_colors[it + 2]);
+		}
+
+		Some(color_sum / 3.0)
+	}
+
 	pub fn get_vert_at(&self, index: usize) -> Vec3 {
-		let tri_index = self.tris_indices[index];
+		let it = self.tris_indices[index] as usize * 3;
 
-		const SZ: u16 = 3;
 		Vec3::new(
-			self.verts[xy_to_it(0, tri_index, SZ)],
-			self.verts[xy_to_it(1, tri_index, SZ)],
-			self.verts[xy_to_it(2, tri_index, SZ)],
+			self.verts[it + 0],
+			self.verts[it + 1],
+			self.verts[it + 2],
 		)
 	}
 
 	pub fn get_normal_at(&self, index: usize) -> Vec3 {
-		let normal_index = self.normal_indices[index];
+		let it = self.normal_indices[index] as usize * 3;
+
+		Vec3::new(
+			self.normals[it + 0],
+			self.normals[it + 1],
+			self.normals[it + 2],
+		)
+	}
+
+	pub fn get_face_normal_at(&self, tri_index: usize) -> Vec3 {
+		let it = tri_index * 3;
 
-		const SZ: u16 = 3;
 		Vec3::new(
-			self.normals[xy_to_it(0, normal_index, SZ)],
-			self.normals[xy_to_it(1, normal_index, SZ)],
-			self.normals[xy_to_it(2, normal_index, SZ)],
+			self.face_normals[it + 0],
+			self.face_normals[it + 1],
+			self.face_normals[it + 2],
 		)
 	}
 
+	// computes whatever normals the file didn't have
+	pub fn compute_missing_normals(&mut self, crease_angle_deg: f32) {
+		if self.face_normals.len() != self.tris_count() * 3 {
+			self.compute_face_normals();
+		}
+
+		if self.normal_indices.is_empty() {
+			self.compute_vertex_normals(crease_angle_deg);
+		}
+	}
+
+	pub fn compute_face_normals(&mut self) {
+		let tris_count = self.tris_count();
+
+		self.face_normals.clear();
+		self.face_normals.reserve(tris_count * 3);
+
+		for tri_i in 0..tris_count {
+			let normal = self.get_area_normal(tri_i);
+			let normal = if normal.squared_magnitude() > 0.0 { normal.normalized() } else { normal };
+
+			self.face_normals.push(normal.x);
+			self.face_normals.push(normal.y);
+			self.face_normals.push(normal.z);
+		}
+	}
+
+	// smooth normals for each triangle corner, adjacent faces only get averaged together if they are in the same
+	// smoothing group or, if the mesh has no smoothing groups, if the angle between them is below the crease angle
+	pub fn compute_vertex_normals(&mut self, crease_angle_deg: f32) {
+		let tris_count = self.tris_count();
+		if self.face_normals.len() != tris_count * 3 {
+			self.compute_face_normals();
+		}
+
+		// triangles around each vertex
+		let mut tris_around_vert = vec![vec![]; self.verts.len() / 3];
+		for (index_i, &vert_index) in self.tris_indices.iter().enumerate() {
+			tris_around_vert[vert_index as usize].push(index_i / 3);
+		}
+
+		// bigger faces weigh more in the average
+		let area_normals: Vec<Vec3> = (0..tris_count).map(|tri_i| self.get_area_normal(tri_i)).collect();
+
+		let has_smoothing_groups = self.tri_smoothing_groups.len() == tris_count;
+		let cos_crease = crease_angle_deg.to_radians().cos();
+
+		let shares_normal = |tri_a: usize, tri_b: usize| -> bool {
+			if tri_a == tri_b { return true }
+
+			if has_smoothing_groups {
+				let group = self.tri_smoothing_groups[tri_a];
+				return group != 0 && group == self.tri_smoothing_groups[tri_b];
+			}
+
+			Vec3::dot_product(&self.get_face_normal_at(tri_a), &self.get_face_normal_at(tri_b)) >= cos_crease
+		};
+
+		let mut normals = vec![];
+		let mut normal_indices = Vec::with_capacity(self.tris_indices.len());
+
+		// corners of the same vertex with the same normal share it
+		let mut normal_index_by_vert = HashMap::<(u32, [u32; 3]), u32>::new();
+
+		for (index_i, &vert_index) in self.tris_indices.iter().enumerate() {
+			let tri_i = index_i / 3;
+
+			let mut normal_sum = Vec3::zero();
+			for &adjacent_tri_i in tris_around_vert[vert_index as usize].iter() {
+				if shares_normal(tri_i, adjacent_tri_i) {
+					normal_sum = normal_sum + area_normals[adjacent_tri_i];
+				}
+			}
+
+			let normal = if normal_sum.squared_magnitude() > 0.0 { normal_sum.normalized() } else { self.get_face_normal_at(tri_i) };
+
+			let key = (vert_index, [normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits()]);
+			let normal_index = *normal_index_by_vert.entry(key).or_insert_with(|| {
+				normals.push(normal.x);
+				normals.push(normal.y);
+				normals.push(normal.z);
+				(normals.len() / 3 - 1) as u32
+			});
+
+			normal_indices.push(normal_index);
+		}
+
+		self.normals = normals;
+		self.normal_indices = normal_indices;
+	}
+
+	// not normalized, its magnitude is twice the triangle's area
+	fn get_area_normal(&self, tri_index: usize) -> Vec3 {
+		let p0 = self.get_vert_at(tri_index * 3 + 0);
+		let p1 = self.get_vert_at(tri_index * 3 + 1);
+		let p2 = self.get_vert_at(tri_index * 3 + 2);
+
+		Vec3::cross_product(&(p1 - p0), &(p2 - p0))
+	}
+
 	pub fn invert_mesh_yz(&mut self) {
 		let mut i = 0;
 		while i < self.verts.len() {
@@ -1,30 +1,148 @@
-use crate::{camera::Camera, mesh::Mesh, renderer::Renderer, terminal::TerminalBuffer, timer::Timer};
+use std::ops::Range;
 
-use crate::{maths::*, rendering::*};
+use crate::{app::App, camera::Camera, mesh::{rgb_luminance, rgb_to_ansi_color, Mesh, MeshPart, DEFAULT_CREASE_ANGLE_DEG}, renderer::Renderer, terminal::TerminalBuffer, timer::Timer};
+
+use crate::{maths::*, rendering::*, render_settings::{MeshFillMode, MeshShadingMode}, terminal::NO_COLOR};
 
 const FILL_CHAR: char = '@';
+// while a part is highlighted, the other ones are drawn with this
+const DIMMED_FILL_CHAR: char = '.';
+
+// towards the light, in world space, it doesn't need to be normalized
+const LIGHT_DIR: Vec3 = Vec3::new(-0.4, 0.8, 0.45);
 
 pub struct ObjRenderer {
 	mesh: Mesh,
 	render: fn(mesh: &Mesh, &mut TerminalBuffer, &Timer, &Camera),
+
+	hidden_parts: Vec<bool>,
+	// the highlighted part, None highlights nothing
+	selected_part: Option<usize>,
 }
 
 impl ObjRenderer {
 	pub fn new(data: Mesh) -> Self {
+		let data = Self::prepare_mesh(data);
+
 		ObjRenderer {
+			hidden_parts: vec![false; data.parts.len()],
+			selected_part: None,
 			mesh: data,
 			render: render_mesh,
 		}
 	}
+
+	// fills in what the renderer needs, it can take a while for big meshes
+	pub fn prepare_mesh(mut data: Mesh) -> Mesh {
+		data.compute_missing_normals(DEFAULT_CREASE_ANGLE_DEG);
+
+		if data.parts.is_empty() {
+			data.parts.push(MeshPart { name: "default".to_owned(), tris: 0..data.tris_count() });
+		}
+
+		data
+	}
+
+	pub fn list_parts(&self) -> impl Iterator<Item = &str> {
+		self.mesh.parts.iter().map(|part| part.name.as_str())
+	}
+
+	pub fn is_part_visible(&self, part_index: usize) -> bool {
+		!self.hidden_parts[part_index]
+	}
+
+	pub fn toggle_part_visibility(&mut self, part_index: usize) {
+		self.hidden_parts[part_index] = !self.hidden_parts[part_index];
+	}
+
+	// cycles nothing -> first part -> ... -> last part -> nothing
+	pub fn select_next_part(&mut self) {
+		self.selected_part = match self.selected_part {
+			None => Some(0),
+			Some(index) if index + 1 < self.mesh.parts.len() => Some(index + 1),
+			Some(_) => None,
+		}
+	}
+
+	pub fn select_prev_part(&mut self) {
+		let parts_count = self.mesh.parts.len();
+		self.selected_part = match self.selected_part {
+			None => Some(parts_count - 1),
+			Some(0) => None,
+			Some(index) => Some(index - 1),
+		}
+	}
 }
 
 impl Renderer for ObjRenderer {
+	// already prepared with 'prepare_mesh'
+	type Scene = Mesh;
+
+	// the hidden and selected parts are kept if the mesh still has them
+	fn set_scene(&mut self, scene: Mesh) {
+		if scene.parts.len() != self.mesh.parts.len() {
+			self.hidden_parts = vec![false; scene.parts.len()];
+			self.selected_part = self.selected_part.filter(|&selected| selected < scene.parts.len());
+		}
+
+		self.mesh = scene;
+	}
+
 	fn render(&self, buf: &mut TerminalBuffer, timer: &Timer, camera: &Camera) {
-		render_mesh(&self.mesh, buf, timer, camera);
+		setup_mesh_matrices(buf, timer, camera);
+
+		for (part_index, part) in self.mesh.parts.iter().enumerate() {
+			if !self.is_part_visible(part_index) { continue }
+
+			let is_dimmed = matches!(self.selected_part, Some(selected) if selected != part_index);
+			if is_dimmed {
+				render_mesh_tris(&self.mesh, part.tris.clone(), DIMMED_FILL_CHAR, false, buf);
+			} else {
+				render_mesh_tris(&self.mesh, part.tris.clone(), FILL_CHAR, true, buf);
+			}
+		}
+	}
+
+	fn consume_user_data(&mut self, app: &mut App) {
+		if app.called_select_next_part {
+			app.called_select_next_part = false;
+			self.select_next_part();
+		}
+
+		if app.called_select_prev_part {
+			app.called_select_prev_part = false;
+			self.select_prev_part();
+		}
+
+		if app.called_toggle_part_visibility {
+			app.called_toggle_part_visibility = false;
+			if let Some(selected) = self.selected_part {
+				self.toggle_part_visibility(selected);
+			}
+		}
+	}
+
+	fn verbose_lines(&self) -> Vec<String> {
+		let parts_count = self.mesh.parts.len();
+		let line = match self.selected_part {
+			None => format!("part: none of {}", parts_count),
+			Some(index) => {
+				let name: String = self.list_parts().nth(index).unwrap_or_default().chars().map(|ch| if ch.is_ascii_graphic() || ch == ' ' { ch } else { '?' }).collect();
+				let hidden = if self.is_part_visible(index) { "" } else { " (hidden)" };
+				format!("part {}/{}: {}{}", index + 1, parts_count, name, hidden)
+			}
+		};
+
+		vec![line]
 	}
 }
 
 pub fn render_mesh(mesh: &Mesh, buf: &mut TerminalBuffer, timer: &Timer, camera: &Camera) {
+	setup_mesh_matrices(buf, timer, camera);
+	render_mesh_tris(mesh, 0..mesh.tris_count(), FILL_CHAR, true, buf);
+}
+
+fn setup_mesh_matrices(buf: &mut TerminalBuffer, timer: &Timer, camera: &Camera) {
 
 	let (pos_x, pos_y, pos_z) = (0.0, 0.0, 0.0);
 
@@ -72,10 +190,18 @@ pub fn render_mesh(mesh: &Mesh, buf: &mut TerminalBuffer, timer: &Timer, camera:
 
 	multiply_4x4_matrices(&mut buf.render_mat, &camera.view_matrix);
 	multiply_4x4_matrices(&mut buf.render_mat, &buf.transf_mat);
+}
+
+// 'tris' is a range of triangles, not of indices, the mesh needs its normals (see 'ObjRenderer::prepare_mesh')
+// shaded triangles are lit when solid, and their material or vertex colors are either the char's color or darken them,
+// the others are drawn with 'fill_char'
+pub fn render_mesh_tris(mesh: &Mesh, tris: Range<usize>, fill_char: char, is_shaded: bool, buf: &mut TerminalBuffer) {
+	let is_solid = matches!(buf.get_mesh_fill_mode(), MeshFillMode::Solid);
+	let is_gouraud = matches!(buf.get_mesh_shading_mode(), MeshShadingMode::Gouraud);
+	let light_dir = LIGHT_DIR.normalized();
 
 	// buf.clear_debug();
-	let num_tris = mesh.tris_indices.len() / 3;
-	for tri_i in 0..num_tris {
+	for tri_i in tris {
 
 		let p0_i = tri_i * 3 + 0;
 		let p1_i = tri_i * 3 + 1;
@@ -86,16 +212,56 @@ pub fn render_mesh(mesh: &Mesh, buf: &mut TerminalBuffer, timer: &Timer, camera:
 		let p1 = mesh.get_vert_at(p1_i);
 		let p2 = mesh.get_vert_at(p2_i);
 
-		let trs_p0 = p0.get_transformed_by_mat4x4_homogeneous(&buf.render_mat);
-		let trs_p1 = p1.get_transformed_by_mat4x4_homogeneous(&buf.render_mat);
-		let trs_p2 = p2.get_transformed_by_mat4x4_homogeneous(&buf.render_mat);
+		let color = if is_shaded { mesh.get_color_at(tri_i) } else { None };
+		let color_luminance = match color {
+			Some(color) if buf.use_color => {
+				buf.fill_color = rgb_to_ansi_color(&color);
+				1.0
+			}
+			Some(color) => rgb_luminance(&color),
+			None => 1.0,
+		};
+
+		if is_solid {
+			let trs_p0 = p0.get_transformed_by_mat4x4_w(&buf.render_mat);
+			let trs_p1 = p1.get_transformed_by_mat4x4_w(&buf.render_mat);
+			let trs_p2 = p2.get_transformed_by_mat4x4_w(&buf.render_mat);
 
-		let screen_p0 = clip_space_to_screen_space(&trs_p0, buf.wid, buf.hei);
-		let screen_p1 = clip_space_to_screen_space(&trs_p1, buf.wid, buf.hei);
-		let screen_p2 = clip_space_to_screen_space(&trs_p2, buf.wid, buf.hei);
+			// w is negative in front of the camera, a triangle that goes behind it would be filled mirrored
+			let is_in_front = trs_p0.w < 0.0 && trs_p1.w < 0.0 && trs_p2.w < 0.0;
+			if is_in_front {
+				let screen_p0 = clip_space_to_screen_space_depth(&trs_p0.homogeneous(), buf.wid, buf.hei);
+				let screen_p1 = clip_space_to_screen_space_depth(&trs_p1.homogeneous(), buf.wid, buf.hei);
+				let screen_p2 = clip_space_to_screen_space_depth(&trs_p2.homogeneous(), buf.wid, buf.hei);
 
-		render_bresenham_line(&screen_p0, &screen_p1, buf, FILL_CHAR);
-		render_bresenham_line(&screen_p1, &screen_p2, buf, FILL_CHAR);
-		render_bresenham_line(&screen_p2, &screen_p0, buf, FILL_CHAR);
+				// the mesh is not rotated by 'setup_mesh_matrices', so its normals are in world space
+				if !is_shaded {
+					render_fill_tri(&screen_p0, &screen_p1, &screen_p2, fill_char, buf);
+				} else if is_gouraud {
+					let luminances = [p0_i, p1_i, p2_i].map(|index| lambert(&mesh.get_normal_at(index), &light_dir) * color_luminance);
+					render_fill_tri_gouraud(&screen_p0, &screen_p1, &screen_p2, luminances, buf);
+				} else {
+					let luminance = lambert(&mesh.get_face_normal_at(tri_i), &light_dir) * color_luminance;
+					render_fill_tri(&screen_p0, &screen_p1, &screen_p2, luminance_to_char(luminance), buf);
+				}
+			}
+		} else {
+			// wireframes are not lit
+			let tri_fill_char = if color.is_some() && !buf.use_color { luminance_to_char(color_luminance) } else { fill_char };
+
+			let trs_p0 = p0.get_transformed_by_mat4x4_homogeneous(&buf.render_mat);
+			let trs_p1 = p1.get_transformed_by_mat4x4_homogeneous(&buf.render_mat);
+			let trs_p2 = p2.get_transformed_by_mat4x4_homogeneous(&buf.render_mat);
+
+			let screen_p0 = clip_space_to_screen_space(&trs_p0, buf.wid, buf.hei);
+			let screen_p1 = clip_space_to_screen_space(&trs_p1, buf.wid, buf.hei);
+			let screen_p2 = clip_space_to_screen_space(&trs_p2, buf.wid, buf.hei);
+
+			render_bresenham_line(&screen_p0, &screen_p1, buf, tri_fill_char);
+			render_bresenham_line(&screen_p1, &screen_p2, buf, tri_fill_char);
+			render_bresenham_line(&screen_p2, &screen_p0, buf, tri_fill_char);
+		}
+
+		buf.fill_color = NO_COLOR;
 	}
-}
\ No newline at end of file
+}
@@ -5,7 +5,8 @@ use crate::{*, camera::Camera, maths::*, timer::Timer, terminal::TerminalBuffer,
 
 pub enum YadePrimitive {
 	Ball(RenderBallData),
-	Line(Line),
+	// and the char it's drawn with
+	Line(Line, char),
 }
 
 pub struct RenderBallData {
@@ -14,6 +15,8 @@ pub struct RenderBallData {
 	pub index: usize,
 	pub screen_pos: IVec2,
 	pub rad: f32,
+	// value of the selected YADE attribute, 0 if there is none
+	pub attribute: f32,
 }
 
 pub struct Line {
@@ -84,6 +87,7 @@ pub fn render_char(ch: char, pos: &UVec2, buffer: &mut TerminalBuffer) {
 
 	let index = xy_to_it(pos.x, pos.y, buffer.wid);
 	encode_char_in(ch, index, &mut buffer.raw_ascii_screen);
+	buffer.raw_color_screen[index] = buffer.fill_color;
 }
 
 pub fn render_string(string: &str, pos: &UVec2, buf: &mut TerminalBuffer) {
@@ -94,6 +98,7 @@ pub fn render_string(string: &str, pos: &UVec2, buf: &mut TerminalBuffer) {
 	let mut index = xy_to_it(pos.x, pos.y, buf.wid);
 	for byte in string.bytes() {
 		buf.raw_ascii_screen[index] = byte;
+		buf.raw_color_screen[index] = buf.fill_color;
 		index += ASCII_BYTES_PER_CHAR
 	}
 }
@@ -139,6 +144,8 @@ pub fn render_straight_x_line(p0x: Int, p1x: Int, y: Int, fill_char: char, buf:
 	debug_assert!(fill_char.len_utf8() == 1, "NOT ASCII");
 	let ascii_fill_char = fill_char as u8;
 
+	buf.raw_color_screen[start..=end_inclusive].fill(buf.fill_color);
+
 	// "safer" version
 	#[cfg(debug_assertions)]
 	buf.raw_ascii_screen[start..=end_inclusive].fill(ascii_fill_char);
@@ -155,6 +162,76 @@ pub fn render_straight_x_line(p0x: Int, p1x: Int, y: Int, fill_char: char, buf:
 	}
 }
 
+// scanline fill of the chars whose centers are inside the triangle, the points are in screen space (x and y in chars)
+// with their depth in z, chars closer than the triangle in 'depth_screen' are kept
+pub fn render_fill_tri(p0: &Vec3, p1: &Vec3, p2: &Vec3, fill_char: char, buf: &mut TerminalBuffer) {
+	render_fill_tri_with([(p0, 0.0), (p1, 0.0), (p2, 0.0)], |_| fill_char, buf);
+}
+
+// like 'render_fill_tri', the luminance of each point (0 to 1) is blended across the triangle to pick the chars
+pub fn render_fill_tri_gouraud(p0: &Vec3, p1: &Vec3, p2: &Vec3, luminances: [Float; 3], buf: &mut TerminalBuffer) {
+	render_fill_tri_with([(p0, luminances[0]), (p1, luminances[1]), (p2, luminances[2])], luminance_to_char, buf);
+}
+
+// 'points' have a value that's interpolated across the triangle and turned into the char of each cell
+fn render_fill_tri_with(mut points: [(&Vec3, Float); 3], value_to_char: impl Fn(Float) -> char, buf: &mut TerminalBuffer) {
+	points.sort_by(|a, b| a.0.y.total_cmp(&b.0.y));
+	let [top, mid, bot] = points;
+
+	// rows whose centers are between the top and the bottom points
+	let first_y = ((top.0.y - 0.5).ceil() as Int).max(0);
+	let last_y = ((bot.0.y - 0.5).floor() as Int).min(buf.hei as Int - 1);
+
+	for y in first_y..=last_y {
+		let center_y = y as Float + 0.5;
+
+		// the long edge goes from top to bottom, the other side changes at the middle point
+		let long_side = tri_edge_at(top, bot, center_y);
+		let short_side = if center_y < mid.0.y { tri_edge_at(top, mid, center_y) } else { tri_edge_at(mid, bot, center_y) };
+
+		if long_side.0 <= short_side.0 {
+			render_depth_tested_x_line(long_side, short_side, y, &value_to_char, buf);
+		} else {
+			render_depth_tested_x_line(short_side, long_side, y, &value_to_char, buf);
+		}
+	}
+}
+
+// x, depth and value where the edge crosses a row
+fn tri_edge_at(start: (&Vec3, Float), end: (&Vec3, Float), y: Float) -> (Float, Float, Float) {
+	let ((start, start_value), (end, end_value)) = (start, end);
+
+	let height = end.y - start.y;
+	let t = if height == 0.0 { 0.0 } else { ((y - start.y) / height).clamp(0.0, 1.0) };
+
+	(lerp_f32(start.x, end.x, t), lerp_f32(start.z, end.z, t), lerp_f32(start_value, end_value, t))
+}
+
+// 'left' and 'right' are the x, depth and value of the ends of the line, the depth and the value are interpolated between them
+fn render_depth_tested_x_line(left: (Float, Float, Float), right: (Float, Float, Float), y: Int, value_to_char: &impl Fn(Float) -> char, buf: &mut TerminalBuffer) {
+	let (left_x, left_z, left_value) = left;
+	let (right_x, right_z, right_value) = right;
+
+	let first_x = ((left_x - 0.5).ceil() as Int).max(0);
+	let last_x = ((right_x - 0.5).floor() as Int).min(buf.wid as Int - 1);
+
+	let width = right_x - left_x;
+	for x in first_x..=last_x {
+		let t = if width == 0.0 { 0.0 } else { (x as Float + 0.5 - left_x) / width };
+		let z = lerp_f32(left_z, right_z, t);
+
+		let index = xy_to_it(x as u16, y as u16, buf.wid);
+		if z >= buf.depth_screen[index] { continue }
+
+		let fill_char = value_to_char(lerp_f32(left_value, right_value, t));
+		debug_assert!(fill_char.len_utf8() == 1, "NOT ASCII");
+
+		buf.depth_screen[index] = z;
+		buf.raw_ascii_screen[index] = fill_char as u8;
+		buf.raw_color_screen[index] = buf.fill_color;
+	}
+}
+
 pub fn render_bresenham_line(p0: &IVec2, p1: &IVec2, buf: &mut TerminalBuffer, fill_char: char) {
 	let last_x = buf.wid - 1;
 	let last_y = buf.hei - 1;
@@ -186,6 +263,7 @@ pub fn render_bresenham_line(p0: &IVec2, p1: &IVec2, buf: &mut TerminalBuffer, f
 			// buf.write_debug(&format!("   bres char {} [{},{}]\n", fill_char, x, y));
 			let index = xy_to_it(x as u16, y as u16, buf.wid);
 			fill_char.encode_utf8(&mut buf.raw_ascii_screen[index..index + ASCII_BYTES_PER_CHAR]);
+			buf.raw_color_screen[index] = buf.fill_color;
 		}
 
 		if x == x1 && y == y1 { return }
@@ -258,6 +336,40 @@ pub fn render_fill_bres_circle(pos: &IVec2, rad_x: f32, fill: char, buf: &mut Te
 	}
 }
 
+// covers the cells of 'render_fill_bres_circle', each one lit by the normal of the sphere in front of it,
+// 'light_dir' is in screen space (X right, Y down, Z away from the camera), 'tint' scales the luminance
+pub fn render_shaded_sphere(pos: &IVec2, rad_x: f32, light_dir: &Vec3, tint: f32, buf: &mut TerminalBuffer) {
+
+	// same radius as the filled circle, in Y it's half of X
+	let rad_y = (rad_x / 2.0) as Int;
+	let rad_x = rad_y * 2;
+
+	// half a cell more, so the cells on the border are inside
+	let (edge_x, edge_y) = (rad_x as f32 + 1.0, rad_y as f32 + 0.5);
+
+	for y in -rad_y..=rad_y {
+		let screen_y = pos.y + y;
+		if screen_y < 0 || screen_y >= buf.hei.into() { continue }
+
+		let dy = y as f32 / edge_y;
+
+		for x in -rad_x..=rad_x {
+			let screen_x = pos.x + x;
+			if screen_x < 0 || screen_x >= buf.wid.into() { continue }
+
+			let dx = x as f32 / edge_x;
+			let sq_len = dx * dx + dy * dy;
+			if sq_len > 1.0 { continue }
+
+			// the sphere bulges towards the camera
+			let normal = Vec3::new(dx, dy, -(1.0 - sq_len).sqrt());
+			let ch = luminance_to_char(lambert(&normal, light_dir) * tint);
+
+			render_char(ch, &UVec2::new(screen_x as u16, screen_y as u16), buf);
+		}
+	}
+}
+
 pub fn render_bres_circle(pos: &IVec2, rad: f32, ch: char, buf: &mut TerminalBuffer) {
 
 	let mut x = 0 as Int;
@@ -5,6 +5,8 @@ pub enum BallFillMode {
 	Height,
 	XZDistance,
 	Index,
+	// one of the attributes read from the YADE header row
+	Attribute,
 }
 
 impl fmt::Display for BallFillMode {
@@ -13,6 +15,7 @@ impl fmt::Display for BallFillMode {
 			BallFillMode::Height     => write!(f, "by height", ),
 			BallFillMode::XZDistance => write!(f, "by 2D distance", ),
 			BallFillMode::Index      => write!(f, "by index", ),
+			BallFillMode::Attribute  => write!(f, "by attribute", ),
 		}
 	}
 }
\ No newline at end of file
@@ -0,0 +1,18 @@
+use core::fmt;
+
+
+pub enum BallShadingMode {
+	// every char is lit by the normal of the sphere in front of it, the fill mode tints it
+	Spheres,
+	// each ball is filled with the single char picked by the fill mode
+	Discs,
+}
+
+impl fmt::Display for BallShadingMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			BallShadingMode::Spheres => write!(f, "shaded spheres", ),
+			BallShadingMode::Discs   => write!(f, "flat discs", ),
+		}
+	}
+}
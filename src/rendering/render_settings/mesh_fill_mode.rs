@@ -0,0 +1,18 @@
+use core::fmt;
+
+
+pub enum MeshFillMode {
+	// filled triangles, hidden by the ones in front of them
+	Solid,
+	// only the edges of the triangles, everything shows through
+	Wireframe,
+}
+
+impl fmt::Display for MeshFillMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MeshFillMode::Solid     => write!(f, "solid", ),
+			MeshFillMode::Wireframe => write!(f, "wireframe", ),
+		}
+	}
+}
@@ -0,0 +1,18 @@
+use core::fmt;
+
+
+pub enum MeshShadingMode {
+	// one char for each face, lit by its normal
+	Flat,
+	// lit at the vertices by their normals, blended across the face
+	Gouraud,
+}
+
+impl fmt::Display for MeshShadingMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MeshShadingMode::Flat    => write!(f, "flat", ),
+			MeshShadingMode::Gouraud => write!(f, "gouraud", ),
+		}
+	}
+}
@@ -2,9 +2,15 @@
 pub mod z_sorting_mode;
 pub mod cull_mode;
 pub mod ball_fill_mode;
+pub mod ball_shading_mode;
 pub mod gizmos_mode;
+pub mod mesh_fill_mode;
+pub mod mesh_shading_mode;
 
 pub use z_sorting_mode::*;
 pub use cull_mode::*;
 pub use ball_fill_mode::*;
+pub use ball_shading_mode::*;
 pub use gizmos_mode::*;
+pub use mesh_fill_mode::*;
+pub use mesh_shading_mode::*;
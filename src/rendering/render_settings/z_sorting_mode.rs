@@ -62,8 +62,8 @@ fn with_lines_and_balls_ordering(a: &(f32, YadePrimitive), b: &(f32, YadePrimiti
 
 	match (primitive_a, primitive_b) {
 		(YadePrimitive::Ball(_), YadePrimitive::Ball(_)) => compare_distances(dist_a, dist_b),
-		(YadePrimitive::Line(_), YadePrimitive::Line(_)) => compare_distances(dist_a, dist_b),
-		(YadePrimitive::Ball(_), YadePrimitive::Line(_)) => line_ord,
-		(YadePrimitive::Line(_), YadePrimitive::Ball(_)) => ball_ord,
+		(YadePrimitive::Line(..), YadePrimitive::Line(..)) => compare_distances(dist_a, dist_b),
+		(YadePrimitive::Ball(_), YadePrimitive::Line(..)) => line_ord,
+		(YadePrimitive::Line(..), YadePrimitive::Ball(_)) => ball_ord,
 	}
 }
\ No newline at end of file
@@ -1,6 +1,41 @@
-use crate::{camera::Camera, terminal::TerminalBuffer, timer::Timer};
+use crate::{app::App, camera::Camera, maths::Vec3, scene_server::ViewCommand, terminal::TerminalBuffer, timer::Timer};
 
 
 pub trait Renderer {
+	// what is drawn, in world coordinates, a new one is swapped in when the input file is reloaded
+	type Scene: Send + 'static;
+
+	fn set_scene(&mut self, scene: Self::Scene);
+
 	fn render(&self, buf: &mut TerminalBuffer, timer: &Timer, camera: &Camera);
+
+	// reacts to the "called_*" data polled from the user that is specific to this renderer
+	fn consume_user_data(&mut self, _app: &mut App) {}
+
+	// advances whatever the renderer animates, the timer's delta time is already scaled by 'time_scale'
+	fn update(&mut self, _timer: &Timer) {}
+
+	// converts back to the units and axes of the file being shown, for the HUD
+	fn world_to_file_units(&self, world_pos: &Vec3) -> Vec3 {
+		*world_pos
+	}
+
+	// extra lines rendered by 'render_verbose'
+	fn verbose_lines(&self) -> Vec<String> {
+		vec![]
+	}
+}
+
+// new scenes for a renderer while it runs, from a file that changed, a stream or the clients of a scene server
+pub trait SceneUpdates<T> {
+	// Some with the newest scene, called every frame so it can't block
+	fn poll(&mut self) -> Option<T>;
+
+	// why the last scene could not be read, shown on the HUD
+	fn error(&self) -> Option<&str>;
+
+	// camera moves and screenshots asked for since the last call
+	fn take_view_commands(&mut self) -> Vec<ViewCommand> {
+		vec![]
+	}
 }
@@ -20,6 +20,15 @@ pub fn clip_space_to_screen_space_f(p: &Vec3, screen_width: u16, screen_height:
 	FVec2::new(screen_x, screen_y)
 }
 
+// x and y in chars, z is kept as the depth
+#[must_use]
+pub fn clip_space_to_screen_space_depth(p: &Vec3, screen_width: u16, screen_height: u16) -> Vec3 {
+	let screen_x = (p.x + 1.0) * 0.5 * screen_width  as f32;
+	let screen_y = (p.y + 1.0) * 0.5 * screen_height as f32;
+
+	Vec3::new(screen_x, screen_y, p.z)
+}
+
 #[must_use]
 pub fn screen_project(vec: &Vec3, render_mat: &[f32], wid: u16, hei: u16) -> IVec2 {
 	let projected_3d = vec.get_transformed_by_mat4x4_homogeneous(render_mat);
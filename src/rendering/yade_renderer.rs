@@ -1,30 +1,194 @@
 use core::{panic, time};
-use std::{thread, time::Duration};
+use std::{collections::HashMap, thread, time::Duration};
 
-use crate::{camera::Camera, file_readers::yade_dem_reader::YadeDemData, renderer::Renderer, terminal::TerminalBuffer, timer::Timer, maths::*, rendering::*};
+use crate::{app::App, camera::Camera, file_readers::{yade_dem_reader::{Ball, YadeDemData}, yade_interactions::{ForceThresholds, YadeInteractions}, yade_timeline::YadeTimeline}, renderer::Renderer, terminal::TerminalBuffer, timer::Timer, maths::*, rendering::*, render_settings::BallShadingMode};
 
 
 const TRIS_WIRE_FILL_CHAR: char = '*';
+const BOX_WIRE_FILL_CHAR: char = '#';
+const WALL_FILL_CHAR: char = '.';
+
+// lines in each direction of a wall's grid, edges included
+const WALL_GRID_LINES: usize = 6;
+// half the size of walls when there are no other bodies to fit them to
+const DEFAULT_WALL_HALF_SIZE: f32 = 4.0;
+
+// cylinders are drawn as a chain of spheres, this far apart in radii
+const CYLINDER_SPHERES_SPACING: f32 = 0.5;
+const MAX_SPHERES_PER_CYLINDER: usize = 64;
+
+// towards the light of shaded spheres, in screen space, it's fixed to the camera so they read as spheres from any angle
+const SPHERE_LIGHT_DIR: Vec3 = Vec3::new(-0.4, -0.8, -0.45);
+
+// F / shift+F moves the minimum force of the contacts drawn by this fraction of the maximum
+const FORCE_THRESHOLD_STEP: f32 = 0.05;
+
+// how many snapshots of a timeline are played per second, at time scale 1
+const PLAYBACK_FRAMES_PER_SEC: f32 = 10.0;
 
 pub struct YadeRenderer {
-	data: YadeDemData,
+	timeline: YadeTimeline,
+	current_frame: usize,
+	// seconds the current frame has been shown for
+	time_in_frame: f32,
+	// index in the 'attribute_names' of the current frame, used by BallFillMode::Attribute
+	selected_attribute: usize,
+	// contacts of each snapshot, a single one is shared by all of them, empty if there are none
+	interactions: Vec<YadeInteractions>,
+	force_thresholds: ForceThresholds,
 }
 
 impl YadeRenderer {
-	pub fn new(data: YadeDemData) -> Self {
-		Self { data }
+	pub fn new(timeline: YadeTimeline) -> Self {
+		Self {
+			timeline,
+			current_frame: 0,
+			time_in_frame: 0.0,
+			selected_attribute: 0,
+			interactions: vec![],
+			force_thresholds: ForceThresholds::default(),
+		}
+	}
+
+	// 'interactions' has one item or one for each snapshot
+	pub fn set_interactions(&mut self, interactions: Vec<YadeInteractions>, force_thresholds: ForceThresholds) {
+		self.interactions = interactions;
+		self.force_thresholds = force_thresholds;
+	}
+
+	fn get_interactions(&self) -> Option<&YadeInteractions> {
+		self.interactions.get(self.current_frame).or(self.interactions.first())
+	}
+
+	// the force of the brightest contacts
+	fn get_max_force(&self) -> f32 {
+		let max_in_file = self.get_interactions().map_or(0.0, |interactions| interactions.max_force);
+		self.force_thresholds.max.unwrap_or(max_in_file)
+	}
+
+	fn get_attribute_names(&self) -> &[String] {
+		&self.timeline.frames[self.current_frame].data.attribute_names
+	}
+
+	fn step_frame(&mut self, forward: bool) {
+		let len = self.timeline.len();
+		self.current_frame = if forward { (self.current_frame + 1) % len } else { (self.current_frame + len - 1) % len };
 	}
 }
 
 impl Renderer for YadeRenderer {
+	type Scene = YadeTimeline;
+
+	// keeps the snapshot shown and the contacts, there can be fewer snapshots than before
+	fn set_scene(&mut self, scene: YadeTimeline) {
+		self.current_frame = self.current_frame.min(scene.len() - 1);
+		self.timeline = scene;
+	}
+
 	fn render(&self, buf: &mut TerminalBuffer, timer: &Timer, camera: &Camera) {
-		render_yade_sorted(&self.data, buf, timer, camera);
+		let data = &self.timeline.frames[self.current_frame].data;
+		let attribute_index = (self.selected_attribute < data.attribute_names.len()).then_some(self.selected_attribute);
+
+		let mut thresholds = self.force_thresholds;
+		thresholds.max = Some(self.get_max_force());
+		let contacts = self.get_interactions().map(|interactions| (interactions, &thresholds));
+
+		render_yade_sorted(data, attribute_index, contacts, buf, timer, camera);
+	}
+
+	fn consume_user_data(&mut self, app: &mut App) {
+		let attributes_count = self.get_attribute_names().len().max(1);
+
+		if app.called_select_next_attribute {
+			app.called_select_next_attribute = false;
+			self.selected_attribute = (self.selected_attribute + 1) % attributes_count;
+		}
+
+		if app.called_select_prev_attribute {
+			app.called_select_prev_attribute = false;
+			self.selected_attribute = (self.selected_attribute + attributes_count - 1) % attributes_count;
+		}
+
+		if app.called_raise_force_threshold {
+			app.called_raise_force_threshold = false;
+			let max_force = self.get_max_force();
+			self.force_thresholds.min = (self.force_thresholds.min + max_force * FORCE_THRESHOLD_STEP).min(max_force);
+		}
+
+		if app.called_lower_force_threshold {
+			app.called_lower_force_threshold = false;
+			let max_force = self.get_max_force();
+			self.force_thresholds.min = (self.force_thresholds.min - max_force * FORCE_THRESHOLD_STEP).max(0.0);
+		}
+
+		if app.called_step_frame_forward {
+			app.called_step_frame_forward = false;
+			self.step_frame(true);
+			self.time_in_frame = 0.0;
+		}
+
+		if app.called_step_frame_back {
+			app.called_step_frame_back = false;
+			self.step_frame(false);
+			self.time_in_frame = 0.0;
+		}
+	}
+
+	// loops through the timeline, pausing the animation (shift + P) sets the time scale to 0 and stops it
+	fn update(&mut self, timer: &Timer) {
+		if self.timeline.len() < 2 { return }
+
+		const FRAME_DURATION: f32 = 1.0 / PLAYBACK_FRAMES_PER_SEC;
+
+		self.time_in_frame += timer.delta_time.as_secs_f32();
+		while self.time_in_frame >= FRAME_DURATION {
+			self.time_in_frame -= FRAME_DURATION;
+			self.step_frame(true);
+		}
+	}
+
+	fn world_to_file_units(&self, world_pos: &Vec3) -> Vec3 {
+		self.timeline.transform.world_to_file_units(world_pos)
+	}
+
+	fn verbose_lines(&self) -> Vec<String> {
+		let mut lines = vec![];
+
+		let data = &self.timeline.frames[self.current_frame].data;
+		if let Some(attribute_name) = data.attribute_names.get(self.selected_attribute) {
+			let ball_values = data.balls.iter().map(|ball| ball.attributes[self.selected_attribute]);
+			let tri_values = data.tris.iter().map(|tri| tri.attributes[self.selected_attribute]);
+
+			// NaN (facets without the attribute) is skipped by min and max
+			let (min, max) = ball_values.chain(tri_values).fold((f32::NAN, f32::NAN), |(min, max), value| (min.min(value), max.max(value)));
+			lines.push(format!("attribute: {} [{:.3}, {:.3}]", attribute_name, min, max));
+		}
+
+		if let Some(interactions) = self.get_interactions() {
+			let shown_count = interactions.interactions.iter().filter(|interaction| interaction.force() >= self.force_thresholds.min).count();
+			lines.push(format!("contacts: {}/{}, force [{:.3}, {:.3}]", shown_count, interactions.interactions.len(), self.force_thresholds.min, self.get_max_force()));
+		}
+
+		if self.timeline.len() < 2 { return lines }
+
+		let frame = &self.timeline.frames[self.current_frame];
+		let iteration_line = match frame.iteration {
+			Some(iteration) => format!("iteration: {}", iteration),
+			None => format!("file: {}", frame.path),
+		};
+
+		lines.push(format!("snapshot: {}/{}", self.current_frame + 1, self.timeline.len()));
+		lines.push(iteration_line);
+
+		lines
 	}
 }
 
 
 
-pub fn render_yade_sorted(yade_data: &YadeDemData, buf: &mut TerminalBuffer, timer: &Timer, camera: &Camera) {
+// 'attribute_index' is the ball attribute painted by BallFillMode::Attribute,
+// 'contacts' are drawn as lines between balls, with their max force set
+pub fn render_yade_sorted(yade_data: &YadeDemData, attribute_index: Option<usize>, contacts: Option<(&YadeInteractions, &ForceThresholds)>, buf: &mut TerminalBuffer, timer: &Timer, camera: &Camera) {
 
 	let (pos_x, pos_y, pos_z) = (0.0, 0.0, 0.0);
 
@@ -80,49 +244,38 @@ pub fn render_yade_sorted(yade_data: &YadeDemData, buf: &mut TerminalBuffer, tim
 
 		if let CullMode::CullBalls = buf.get_cull_mode() { continue }
 
-		let clip_pos = ball.pos.get_transformed_by_mat4x4_homogeneous(&buf.render_mat);
-
-		let transformed_pos = ball.pos.get_transformed_by_mat4x4_discard_w(&buf.transf_mat);
-
-		// culling balls too far away
-		if transformed_pos.squared_dist_to(&camera.position) > SQUARED_ZF { continue }
-
-		// culling balls behind the camera
-		let ball_to_cam = camera.position - transformed_pos;
-		let dot = Vec3::dot_product(&camera.forward, &ball_to_cam);
-		if dot < 0.0 { continue }
-
-		let reference_pos = transformed_pos.add_vec(&(camera.side * rad_scaled_3d));
-		let reference_pos_proj = reference_pos.get_transformed_by_mat4x4_homogeneous(&render_mat_without_transform);
-		let reference_pos_proj_screen_f = clip_space_to_screen_space_f(&reference_pos_proj, buf.wid, buf.hei);
+		let attribute = attribute_index.map_or(0.0, |attribute_index| ball.attributes[attribute_index]);
+		let Some((sq_dist_to_camera, transformed_pos, render_data)) = project_ball(&ball.pos, rad_scaled_3d, index, attribute, &render_mat_without_transform, camera, buf) else { continue };
 
-		let screen_pos_f32 = clip_space_to_screen_space_f(&clip_pos, buf.wid, buf.hei);
-		let rad_2d = (reference_pos_proj_screen_f.x - screen_pos_f32.x).abs();
-		// buf.write_debug(&format!("{:?} scr {:?}\n", trs_pos_sd_projected_screen_f, screen_pos_f32));
+		ball_painter.find_min_max(&transformed_pos, render_data.sq_dist_to_camera, attribute);
+		render_data_by_dist.push((sq_dist_to_camera, YadePrimitive::Ball(render_data)));
+	}
 
-		if cull_circle(&screen_pos_f32, rad_2d, buf) { continue; }
+	// cylinders are capsules, drawn with spheres along them, they are numbered after the balls
+	for (cylinder_index, cylinder) in yade_data.cylinders.iter().enumerate() {
 
-		let screen_pos = IVec2::from(&screen_pos_f32);
+		let rad_scaled_3d = cylinder.rad * scale;
+		smallest_rad_3d = smallest_rad_3d.min(rad_scaled_3d);
 
-		let sq_dist_to_camera = transformed_pos.squared_dist_to(&camera.position);
+		if let CullMode::CullBalls = buf.get_cull_mode() { continue }
 
-		// DEBUG
-		// safe_render_string_signed(&format!("C {:.2}", sq_dist_to_camera), screen_pos.x, (screen_pos_f32.y as f32 - rad * 3.5) as i32, buf);
+		let index = yade_data.balls.len() + cylinder_index;
+		// NaN is skipped when finding the attribute range, cylinders have no attributes
+		let attribute = if attribute_index.is_some() { f32::NAN } else { 0.0 };
 
-		let cam_to_pos_vec = transformed_pos - camera.position;
-		let sq_dist_xz = cam_to_pos_vec.x * cam_to_pos_vec.x + cam_to_pos_vec.z * cam_to_pos_vec.z;
+		let p0_to_p1 = cylinder.p1 - cylinder.p0;
+		let spacing = (rad_scaled_3d * CYLINDER_SPHERES_SPACING).max(f32::EPSILON);
+		let segments = ((p0_to_p1.magnitude() / spacing).ceil() as usize).clamp(1, MAX_SPHERES_PER_CYLINDER - 1);
 
-		ball_painter.find_min_max(&transformed_pos, sq_dist_xz);
+		for segment_i in 0..=segments {
+			let t = segment_i as f32 / segments as f32;
+			let pos = cylinder.p0 + p0_to_p1 * t;
 
-		let render_data = RenderBallData {
-			height: transformed_pos.y,
-			sq_dist_to_camera: sq_dist_xz,
-			rad: rad_2d,
-			screen_pos,
-			index,
-		};
+			let Some((sq_dist_to_camera, transformed_pos, render_data)) = project_ball(&pos, rad_scaled_3d, index, attribute, &render_mat_without_transform, camera, buf) else { continue };
 
-		render_data_by_dist.push((sq_dist_to_camera, YadePrimitive::Ball(render_data)));
+			ball_painter.find_min_max(&transformed_pos, render_data.sq_dist_to_camera, attribute);
+			render_data_by_dist.push((sq_dist_to_camera, YadePrimitive::Ball(render_data)));
+		}
 	}
 
 
@@ -150,18 +303,49 @@ pub fn render_yade_sorted(yade_data: &YadeDemData, buf: &mut TerminalBuffer, tim
 		let trs_p2 = tri.p2.get_transformed_by_mat4x4_discard_w(&buf.transf_mat);
 
 		if let ZSortingMode::Optimized = buf.get_sorting_mode() {
-			cut_line_and_insert(&trs_p0, &trs_p1, smallest_rad_3d, &render_mat_without_transform, &camera, &mut render_data_by_dist, buf);
-			cut_line_and_insert(&trs_p1, &trs_p2, smallest_rad_3d, &render_mat_without_transform, &camera, &mut render_data_by_dist, buf);
-			cut_line_and_insert(&trs_p2, &trs_p0, smallest_rad_3d, &render_mat_without_transform, &camera, &mut render_data_by_dist, buf);
+			cut_line_and_insert(&trs_p0, &trs_p1, TRIS_WIRE_FILL_CHAR, smallest_rad_3d, &render_mat_without_transform, &camera, &mut render_data_by_dist, buf);
+			cut_line_and_insert(&trs_p1, &trs_p2, TRIS_WIRE_FILL_CHAR, smallest_rad_3d, &render_mat_without_transform, &camera, &mut render_data_by_dist, buf);
+			cut_line_and_insert(&trs_p2, &trs_p0, TRIS_WIRE_FILL_CHAR, smallest_rad_3d, &render_mat_without_transform, &camera, &mut render_data_by_dist, buf);
 		} else {
 			// TODO: take ownership
 			triangle_lines_distance_fn(&trs_p0, &trs_p1, &trs_p2, camera, &screen_tri, &mut render_data_by_dist);
 		}
 	}
 
+	// boxes and walls are culled with the tris, they are wireframes too
+	let mut wire_lines = match buf.get_cull_mode() {
+		CullMode::CullTris => vec![],
+		_ => get_cuboid_and_wall_lines(yade_data),
+	};
+
+	// contacts are never culled, culling balls shows the force chains
+	if let Some((interactions, thresholds)) = contacts {
+		wire_lines.extend(get_contact_lines(yade_data, interactions, thresholds));
+	}
+
+	for (p0, p1, fill_char) in wire_lines.iter() {
+
+		let Some(line) = cull_line_into_screen_space(p0, p1, camera, buf) else { continue };
+
+		let trs_p0 = p0.get_transformed_by_mat4x4_discard_w(&buf.transf_mat);
+		let trs_p1 = p1.get_transformed_by_mat4x4_discard_w(&buf.transf_mat);
+
+		let dist_p0 = trs_p0.squared_dist_to(&camera.position);
+		let dist_p1 = trs_p1.squared_dist_to(&camera.position);
+
+		match buf.get_sorting_mode() {
+			ZSortingMode::Optimized => cut_line_and_insert(&trs_p0, &trs_p1, *fill_char, smallest_rad_3d, &render_mat_without_transform, camera, &mut render_data_by_dist, buf),
+			ZSortingMode::FarthestPoint => render_data_by_dist.push((dist_p0.max(dist_p1), YadePrimitive::Line(line, *fill_char))),
+			_ => render_data_by_dist.push((dist_p0.min(dist_p1), YadePrimitive::Line(line, *fill_char))),
+		}
+	}
+
 
 	render_data_by_dist.sort_by(buf.get_sorting_mode().get_sorting_fn());
 
+	let are_balls_shaded = matches!(buf.get_ball_shading_mode(), BallShadingMode::Spheres);
+	let sphere_light_dir = SPHERE_LIGHT_DIR.normalized();
+
 	let mut lines = 0;
 	for data_to_render_by_dist in render_data_by_dist.iter() {
 
@@ -171,11 +355,16 @@ pub fn render_yade_sorted(yade_data: &YadeDemData, buf: &mut TerminalBuffer, tim
 		// buf.write_debug(&format!("cur {:.2} min {:.2} max {:.2} \n", dist_sq, min_dist_sq, max_dist_sq));
 		match data_to_render {
 			YadePrimitive::Ball(ball_data) => {
-				let letter = ball_painter.get_fill_letter(&ball_data);
-				render_fill_bres_circle(&ball_data.screen_pos, ball_data.rad, letter, buf);
+				if are_balls_shaded {
+					let tint = ball_painter.get_sphere_tint(ball_data);
+					render_shaded_sphere(&ball_data.screen_pos, ball_data.rad, &sphere_light_dir, tint, buf);
+				} else {
+					let letter = ball_painter.get_fill_letter(&ball_data);
+					render_fill_bres_circle(&ball_data.screen_pos, ball_data.rad, letter, buf);
+				}
 			},
-			YadePrimitive::Line(line) => {
-				render_bresenham_line(&line.p0, &line.p1, buf, TRIS_WIRE_FILL_CHAR);
+			YadePrimitive::Line(line, fill_char) => {
+				render_bresenham_line(&line.p0, &line.p1, buf, *fill_char);
 
 				// TODO: DEBUG OPTION different line drawing modes
 				// buf.write_debug(&format!(" LINE {}, {} %\n", lines, lines % 2));
@@ -193,9 +382,139 @@ pub fn render_yade_sorted(yade_data: &YadeDemData, buf: &mut TerminalBuffer, tim
 	// buf.write_debug(&format!("lines {}\n", lines));
 }
 
+// (sq distance to the camera, transformed position, render data) of a ball, None if it's culled
+fn project_ball(
+	pos: &Vec3,
+	rad_scaled_3d: f32,
+	index: usize,
+	attribute: f32,
+	render_mat_without_transform: &[f32],
+	camera: &Camera,
+	buf: &mut TerminalBuffer
+) -> Option<(f32, Vec3, RenderBallData)> {
+
+	let clip_pos = pos.get_transformed_by_mat4x4_homogeneous(&buf.render_mat);
+
+	let transformed_pos = pos.get_transformed_by_mat4x4_discard_w(&buf.transf_mat);
+
+	// culling balls too far away
+	if transformed_pos.squared_dist_to(&camera.position) > SQUARED_ZF { return None }
+
+	// culling balls behind the camera
+	let ball_to_cam = camera.position - transformed_pos;
+	let dot = Vec3::dot_product(&camera.forward, &ball_to_cam);
+	if dot < 0.0 { return None }
+
+	let reference_pos = transformed_pos.add_vec(&(camera.side * rad_scaled_3d));
+	let reference_pos_proj = reference_pos.get_transformed_by_mat4x4_homogeneous(render_mat_without_transform);
+	let reference_pos_proj_screen_f = clip_space_to_screen_space_f(&reference_pos_proj, buf.wid, buf.hei);
+
+	let screen_pos_f32 = clip_space_to_screen_space_f(&clip_pos, buf.wid, buf.hei);
+	let rad_2d = (reference_pos_proj_screen_f.x - screen_pos_f32.x).abs();
+	// buf.write_debug(&format!("{:?} scr {:?}\n", trs_pos_sd_projected_screen_f, screen_pos_f32));
+
+	if cull_circle(&screen_pos_f32, rad_2d, buf) { return None }
+
+	let screen_pos = IVec2::from(&screen_pos_f32);
+
+	let sq_dist_to_camera = transformed_pos.squared_dist_to(&camera.position);
+
+	// DEBUG
+	// safe_render_string_signed(&format!("C {:.2}", sq_dist_to_camera), screen_pos.x, (screen_pos_f32.y as f32 - rad * 3.5) as i32, buf);
+
+	let cam_to_pos_vec = transformed_pos - camera.position;
+	let sq_dist_xz = cam_to_pos_vec.x * cam_to_pos_vec.x + cam_to_pos_vec.z * cam_to_pos_vec.z;
+
+	let render_data = RenderBallData {
+		height: transformed_pos.y,
+		sq_dist_to_camera: sq_dist_xz,
+		rad: rad_2d,
+		screen_pos,
+		index,
+		attribute,
+	};
+
+	Some((sq_dist_to_camera, transformed_pos, render_data))
+}
+
+// (p0, p1, fill char) of the edges of every box and the grid of every wall
+fn get_cuboid_and_wall_lines(yade_data: &YadeDemData) -> Vec<(Vec3, Vec3, char)> {
+	let mut lines = vec![];
+
+	for cuboid in yade_data.cuboids.iter() {
+		// bit 0, 1, 2 of the index choose the min or max side in x, y, z
+		let corners: Vec<Vec3> = (0..8).map(|corner_i| {
+			let sign = |bit: usize| if corner_i & (1 << bit) == 0 { -1.0 } else { 1.0 };
+			cuboid.center + Vec3::new(cuboid.extents.x * sign(0), cuboid.extents.y * sign(1), cuboid.extents.z * sign(2))
+		}).collect();
+
+		// an edge joins two corners that differ in a single axis
+		for corner_i in 0..8 {
+			for bit in 0..3 {
+				let other_i = corner_i | (1 << bit);
+				if other_i == corner_i { continue }
+				lines.push((corners[corner_i], corners[other_i], BOX_WIRE_FILL_CHAR));
+			}
+		}
+	}
+
+	if yade_data.walls.is_empty() { return lines }
+
+	// walls are infinite, they are drawn as far as the other bodies go
+	let default_half_size = Vec3::new(DEFAULT_WALL_HALF_SIZE, DEFAULT_WALL_HALF_SIZE, DEFAULT_WALL_HALF_SIZE);
+	let (min, max) = yade_data.get_bounds().unwrap_or((Vec3::zero() - default_half_size, default_half_size));
+	let (min, max) = ([min.x, min.y, min.z], [max.x, max.y, max.z]);
+
+	for wall in yade_data.walls.iter() {
+		let (u_axis, v_axis) = ((wall.axis + 1) % 3, (wall.axis + 2) % 3);
+
+		let point_at = |u: f32, v: f32| {
+			let mut point = [wall.pos.x, wall.pos.y, wall.pos.z];
+			point[u_axis] = u;
+			point[v_axis] = v;
+			Vec3::new(point[0], point[1], point[2])
+		};
+
+		for line_i in 0..WALL_GRID_LINES {
+			let t = line_i as f32 / (WALL_GRID_LINES - 1) as f32;
+			let u = min[u_axis] + (max[u_axis] - min[u_axis]) * t;
+			let v = min[v_axis] + (max[v_axis] - min[v_axis]) * t;
+
+			lines.push((point_at(u, min[v_axis]), point_at(u, max[v_axis]), WALL_FILL_CHAR));
+			lines.push((point_at(min[u_axis], v), point_at(max[u_axis], v), WALL_FILL_CHAR));
+		}
+	}
+
+	lines
+}
+
+// (p0, p1, fill char) of the contacts between two balls, the stronger the brighter
+fn get_contact_lines(yade_data: &YadeDemData, interactions: &YadeInteractions, thresholds: &ForceThresholds) -> Vec<(Vec3, Vec3, char)> {
+	let balls_by_id: HashMap<usize, &Ball> = yade_data.balls.iter().map(|ball| (ball.id, ball)).collect();
+
+	let max_force = thresholds.max.unwrap_or(interactions.max_force);
+	let range = max_force - thresholds.min;
+
+	let mut lines = vec![];
+	for interaction in interactions.interactions.iter() {
+		let force = interaction.force();
+		if force < thresholds.min { continue }
+
+		// contacts with facets, walls and so on are not drawn
+		let (Some(ball0), Some(ball1)) = (balls_by_id.get(&interaction.id0), balls_by_id.get(&interaction.id1)) else { continue };
+
+		let luminance = if range > 0.0 { (force - thresholds.min) / range } else { 1.0 };
+		lines.push((ball0.pos, ball1.pos, luminance_to_char(luminance)));
+	}
+
+	lines
+}
+
+#[allow(clippy::too_many_arguments)]
 fn cut_line_and_insert(
 	p0: &Vec3,
 	p1: &Vec3,
+	fill_char: char,
 	segment_threshold: f32,
 	render_mat: &[f32], 
 	camera: &Camera,
@@ -239,48 +558,48 @@ fn cut_line_and_insert(
 
 		last_point = Some(seg_i_p1);
 
-		let line = YadePrimitive::Line(Line { p0: seg_p0, p1: seg_p1 });
+		let line = YadePrimitive::Line(Line { p0: seg_p0, p1: seg_p1 }, fill_char);
 		render_data.push( (dist, line) );
 	}
 
-	// draws from the last drawn point to p1
-	let last_drawn_p = last_point.unwrap();
+	// draws from the last drawn point to p1, or all of it if it's shorter than a segment
+	let last_drawn_p = last_point.unwrap_or(*p0);
 
 	let last_drawn_p_scr = screen_project(&last_drawn_p, &render_mat, buf.wid, buf.hei);
 	let p1_scr = screen_project(&p1, &render_mat, buf.wid, buf.hei);
 
 	let dist = last_drawn_p.squared_dist_to(&camera.position).min(p1.squared_dist_to(&camera.position));
 
-	let line = YadePrimitive::Line(Line { p0: last_drawn_p_scr, p1: p1_scr });
+	let line = YadePrimitive::Line(Line { p0: last_drawn_p_scr, p1: p1_scr }, fill_char);
 	render_data.push( (dist, line) );
 }
 
 
 fn min_of_each_tri_line(trs_p0: &Vec3, trs_p1: &Vec3, trs_p2: &Vec3, camera: &Camera, screen_tri: &ScreenTri, render_data: &mut Vec::<(f32, YadePrimitive)>) {
 	let dist0 = trs_p0.squared_dist_to(&camera.position).min(trs_p1.squared_dist_to(&camera.position));
-	let line_p0_p1 = YadePrimitive::Line( (screen_tri.p0.clone(), screen_tri.p1.clone()).into() );
+	let line_p0_p1 = YadePrimitive::Line( (screen_tri.p0.clone(), screen_tri.p1.clone()).into(), TRIS_WIRE_FILL_CHAR );
 	render_data.push((dist0, line_p0_p1));
 
 	let dist1 = trs_p1.squared_dist_to(&camera.position).min(trs_p2.squared_dist_to(&camera.position));
-	let line_p1_p2 = YadePrimitive::Line( (screen_tri.p1.clone(), screen_tri.p2.clone()).into() );
+	let line_p1_p2 = YadePrimitive::Line( (screen_tri.p1.clone(), screen_tri.p2.clone()).into(), TRIS_WIRE_FILL_CHAR );
 	render_data.push((dist1, line_p1_p2));
 
 	let dist2 = trs_p2.squared_dist_to(&camera.position).min(trs_p0.squared_dist_to(&camera.position));
-	let line_p2_p0 = YadePrimitive::Line( (screen_tri.p2.clone(), screen_tri.p0.clone()).into() );
+	let line_p2_p0 = YadePrimitive::Line( (screen_tri.p2.clone(), screen_tri.p0.clone()).into(), TRIS_WIRE_FILL_CHAR );
 	render_data.push((dist2, line_p2_p0));
 }
 
 fn max_of_each_tri_line(trs_p0: &Vec3, trs_p1: &Vec3, trs_p2: &Vec3, camera: &Camera, screen_tri: &ScreenTri, render_data: &mut Vec::<(f32, YadePrimitive)>) {
 	let dist0 = trs_p0.squared_dist_to(&camera.position).max(trs_p1.squared_dist_to(&camera.position));
-	let line_p0_p1 = YadePrimitive::Line( (screen_tri.p0.clone(), screen_tri.p1.clone()).into() );
+	let line_p0_p1 = YadePrimitive::Line( (screen_tri.p0.clone(), screen_tri.p1.clone()).into(), TRIS_WIRE_FILL_CHAR );
 	render_data.push((dist0, line_p0_p1));
 
 	let dist1 = trs_p1.squared_dist_to(&camera.position).max(trs_p2.squared_dist_to(&camera.position));
-	let line_p1_p2 = YadePrimitive::Line( (screen_tri.p1.clone(), screen_tri.p2.clone()).into() );
+	let line_p1_p2 = YadePrimitive::Line( (screen_tri.p1.clone(), screen_tri.p2.clone()).into(), TRIS_WIRE_FILL_CHAR );
 	render_data.push((dist1, line_p1_p2));
 
 	let dist2 = trs_p2.squared_dist_to(&camera.position).max(trs_p0.squared_dist_to(&camera.position));
-	let line_p2_p0 = YadePrimitive::Line( (screen_tri.p2.clone(), screen_tri.p0.clone()).into() );
+	let line_p2_p0 = YadePrimitive::Line( (screen_tri.p2.clone(), screen_tri.p0.clone()).into(), TRIS_WIRE_FILL_CHAR );
 	render_data.push((dist2, line_p2_p0));
 }
 
@@ -380,6 +699,7 @@ pub fn render_yade(yade_data: &YadeDemData, buf: &mut TerminalBuffer, timer: &Ti
 			rad,
 			screen_pos: screen_pos_f32.into(),
 			index,
+			attribute: 0.0,
 		};
 
 		indices_by_dist.push(render_data);
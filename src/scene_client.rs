@@ -0,0 +1,116 @@
+// for simulation scripts, the viewer itself only uses it for "peekdem send", the other commands are allowed to be unused here
+
+use std::{io::{self, BufRead, BufReader, Read, Write}, net::TcpStream};
+
+use crate::{file_readers::yade_dem_reader::Ball, maths::{Float, Vec3}, scene_server::ListenAddress};
+
+
+// sends the commands of the 'scene_server' protocol to a viewer started with "--listen", every call waits for its reply
+pub struct SceneClient {
+	replies: BufReader<Box<dyn Read + Send>>,
+	commands: Box<dyn Write + Send>,
+}
+
+impl SceneClient {
+	pub fn connect(address: &ListenAddress) -> io::Result<Self> {
+		match address {
+			ListenAddress::Tcp(port) => {
+				let stream = TcpStream::connect(("127.0.0.1", *port))?;
+				Ok(Self::from_streams(Box::new(stream.try_clone()?), Box::new(stream)))
+			}
+			ListenAddress::Unix(path) => Self::connect_unix(path),
+		}
+	}
+
+	#[cfg(unix)]
+	fn connect_unix(path: &str) -> io::Result<Self> {
+		let stream = std::os::unix::net::UnixStream::connect(path)?;
+		Ok(Self::from_streams(Box::new(stream.try_clone()?), Box::new(stream)))
+	}
+
+	#[cfg(not(unix))]
+	fn connect_unix(_path: &str) -> io::Result<Self> {
+		Err(io::Error::new(io::ErrorKind::Unsupported, "Unix domain sockets are not supported here, connect to a port"))
+	}
+
+	fn from_streams(replies: Box<dyn Read + Send>, commands: Box<dyn Write + Send>) -> Self {
+		Self { replies: BufReader::new(replies), commands }
+	}
+
+	// replaces the bodies shown with 'balls', in file units, their attributes are not sent
+	#[allow(dead_code)]
+	pub fn send_balls(&mut self, iteration: Option<u64>, balls: &[Ball]) -> io::Result<()> {
+		let header = "# type, x, y, z, r".to_owned();
+		let lines = balls.iter().map(|ball| format!("0, {}, {}, {}, {}", ball.pos.x, ball.pos.y, ball.pos.z, ball.rad));
+		self.send_frame_lines(iteration, std::iter::once(header).chain(lines))
+	}
+
+	// replaces the bodies shown with YADE lines, like the ones of a snapshot file
+	pub fn send_frame_lines<S: AsRef<str>>(&mut self, iteration: Option<u64>, lines: impl IntoIterator<Item = S>) -> io::Result<()> {
+		let mut frame = match iteration {
+			Some(iteration) => format!("frame {}\n", iteration),
+			None => "frame\n".to_owned(),
+		};
+
+		for line in lines {
+			let line = line.as_ref();
+			// it would end the frame early
+			if line.trim() == "end" { continue }
+
+			frame.push_str(line);
+			frame.push('\n');
+		}
+		frame.push_str("end");
+
+		self.send_command(&frame)
+	}
+
+	// 'index' is the order of the ball in the last frame sent
+	#[allow(dead_code)]
+	pub fn move_ball(&mut self, index: usize, pos: Vec3, rad: Option<Float>) -> io::Result<()> {
+		match rad {
+			Some(rad) => self.send_command(&format!("ball {} {} {} {} {}", index, pos.x, pos.y, pos.z, rad)),
+			None => self.send_command(&format!("ball {} {} {} {}", index, pos.x, pos.y, pos.z)),
+		}
+	}
+
+	#[allow(dead_code)]
+	pub fn reset_camera(&mut self) -> io::Result<()> {
+		self.send_command("camera reset")
+	}
+
+	// degrees around the X and Y axes, 'dist' is from the center of the scene
+	#[allow(dead_code)]
+	pub fn orbit_camera(&mut self, rot_x: f32, rot_y: f32, dist: Option<f32>) -> io::Result<()> {
+		match dist {
+			Some(dist) => self.send_command(&format!("camera orbit {} {} {}", rot_x, rot_y, dist)),
+			None => self.send_command(&format!("camera orbit {} {}", rot_x, rot_y)),
+		}
+	}
+
+	// the viewer saves "screenshot.txt" after it draws its next frame
+	#[allow(dead_code)]
+	pub fn take_screenshot(&mut self) -> io::Result<()> {
+		self.send_command("screenshot")
+	}
+
+	// a command line (or the lines of a frame), an "error" reply is returned as an 'InvalidInput' error with its message
+	pub fn send_command(&mut self, command: &str) -> io::Result<()> {
+		self.commands.write_all(command.as_bytes())?;
+		self.commands.write_all(b"\n")?;
+		self.commands.flush()?;
+
+		let mut reply = String::new();
+		if self.replies.read_line(&mut reply)? == 0 {
+			return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the viewer closed the connection"));
+		}
+
+		match reply.trim_end() {
+			"ok" => Ok(()),
+			reply => {
+				let msg = reply.strip_prefix("error ").unwrap_or(reply);
+				Err(io::Error::new(io::ErrorKind::InvalidInput, msg.to_owned()))
+			}
+		}
+	}
+}
@@ -0,0 +1,480 @@
+use std::{fmt::{self, Display}, io::{self, BufRead, BufReader, Read, Write}, net::TcpListener, str::{FromStr, SplitWhitespace}, sync::{Arc, Mutex}, thread};
+
+use crate::{app::App, camera::Camera, file_readers::{coordinate_convention::{CoordinateConvention, WorldTransform}, yade_dem_reader::{YadeDemData, YadeLineReader}, yade_timeline::{YadeFrame, YadeTimeline}}, maths::Vec3, renderer::SceneUpdates};
+
+
+// "peekdem --listen <socket path | port>" takes commands from simulation scripts while it shows the scene,
+// on a Unix domain socket or on a TCP port of localhost, every client has its own connection.
+// the protocol is made of text lines, one command per line, every command is answered with "ok" or "error <message>":
+//
+//   frame [<iteration>]                  replaces the bodies shown with the YADE lines after it ("0, x, y, z, r, ...",
+//     0, 1.0, 2.0, 0.5, 0.1              a header row can come first), up to a line with "end", in file units
+//     end
+//   ball <index> <x> <y> <z> [<radius>]  moves a ball of the last frame, the index is its order in it (from 0)
+//   camera reset                         the camera goes back to where it started, like the R key
+//   camera orbit <x> <y> [<distance>]    orbits the center of the scene, angles in degrees around the X and Y axes
+//   screenshot                           saves the screen to "screenshot.txt" after the next frame is drawn
+//
+// blank lines and "#" comments between commands are skipped, 'SceneClient' sends these commands from Rust
+
+// the socket files of the servers of this process, removed when they are dropped or the viewer quits
+static UNIX_SOCKET_PATHS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+#[derive(Clone, Debug)]
+pub enum ListenAddress {
+	Unix(String),
+	Tcp(u16),
+}
+
+impl ListenAddress {
+	// a number is a TCP port, anything else a socket path
+	pub fn from_arg(arg: &str) -> Option<Self> {
+		if arg.is_empty() { return None }
+
+		match arg.parse() {
+			Ok(port) => Some(Self::Tcp(port)),
+			Err(_) => Some(Self::Unix(arg.to_owned())),
+		}
+	}
+}
+
+impl Display for ListenAddress {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Unix(path) => write!(f, "{}", path),
+			Self::Tcp(port) => write!(f, "127.0.0.1:{}", port),
+		}
+	}
+}
+
+// what the render loop does for a client, besides showing its bodies
+pub enum ViewCommand {
+	ResetCamera,
+	// radians, the distance is from the center of the orbit
+	OrbitCamera { rot_x: f32, rot_y: f32, dist: Option<f32> },
+	Screenshot,
+}
+
+impl ViewCommand {
+	// before the camera reads the user input of the frame
+	pub fn apply(self, camera: &mut Camera, app: &mut App) {
+		match self {
+			Self::ResetCamera => app.called_reset_camera = true,
+			Self::OrbitCamera { rot_x, rot_y, dist } => {
+				if app.is_free_mov() {
+					app.toggle_free_mov(camera);
+				}
+				camera.set_orbit(rot_x, rot_y, dist);
+			}
+			// saved after the frame is drawn
+			Self::Screenshot => app.called_take_screenshot = true,
+		}
+	}
+}
+
+// the scene built from the commands of every client, read by the render loop
+struct ServerState {
+	// in file units
+	data: YadeDemData,
+	iteration: Option<u64>,
+	// false until a client changes the bodies
+	has_changed: bool,
+	view_commands: Vec<ViewCommand>,
+	// the last command that failed, cleared by one that works
+	error: Option<String>,
+}
+
+// listens for clients on other threads, the bodies they push are drawn by the 'YadeRenderer'
+pub struct SceneServer {
+	// with the port that was picked for port 0
+	address: ListenAddress,
+	state: Arc<Mutex<ServerState>>,
+	convention: CoordinateConvention,
+	// found from the first frame with bodies, so the next ones line up with it
+	transform: Option<WorldTransform>,
+	error: Option<String>,
+}
+
+impl SceneServer {
+	// 'data' is what's shown before a client pushes a frame, in file units, with the transform it's shown with
+	pub fn listen(address: &ListenAddress, data: YadeDemData, transform: WorldTransform, convention: CoordinateConvention) -> io::Result<Self> {
+		// the one of an empty scene is not kept
+		let transform = Some(transform).filter(|_| data.bodies_count() > 0);
+		let state = Arc::new(Mutex::new(ServerState { data, iteration: None, has_changed: false, view_commands: vec![], error: None }));
+
+		let address = match address {
+			ListenAddress::Tcp(port) => {
+				let listener = TcpListener::bind(("127.0.0.1", *port))?;
+				// port 0 is any free port
+				let bound_port = listener.local_addr()?.port();
+				let state = Arc::clone(&state);
+				thread::spawn(move || accept_clients(listener.incoming(), &state));
+				ListenAddress::Tcp(bound_port)
+			}
+			ListenAddress::Unix(path) => {
+				listen_unix(path, &state)?;
+				UNIX_SOCKET_PATHS.lock().unwrap().push(path.clone());
+				address.clone()
+			}
+		};
+
+		Ok(Self { address, state, convention, transform, error: None })
+	}
+}
+
+impl Drop for SceneServer {
+	fn drop(&mut self) {
+		let ListenAddress::Unix(path) = &self.address else { return };
+
+		let mut socket_paths = UNIX_SOCKET_PATHS.lock().unwrap();
+		if let Some(i) = socket_paths.iter().position(|socket_path| socket_path == path) {
+			socket_paths.swap_remove(i);
+			let _ = std::fs::remove_file(path);
+		}
+	}
+}
+
+// quitting exits the process without dropping the server, a socket file left behind would look like a viewer still listening
+pub fn remove_unix_sockets() {
+	for path in UNIX_SOCKET_PATHS.lock().unwrap().drain(..) {
+		let _ = std::fs::remove_file(path);
+	}
+}
+
+impl SceneUpdates<YadeTimeline> for SceneServer {
+	// the bodies as the clients left them, if they changed since the last poll
+	fn poll(&mut self) -> Option<YadeTimeline> {
+		let mut state = self.state.lock().unwrap();
+		self.error.clone_from(&state.error);
+
+		if !state.has_changed { return None }
+		state.has_changed = false;
+
+		let frame = YadeFrame { path: self.address.to_string(), iteration: state.iteration, data: state.data.clone() };
+		drop(state);
+
+		let transform = match self.transform {
+			Some(transform) => transform,
+			None => {
+				let transform = WorldTransform::new(&self.convention, &frame.data);
+				if frame.data.bodies_count() > 0 {
+					self.transform = Some(transform);
+				}
+				transform
+			}
+		};
+
+		Some(YadeTimeline::from_frames_with_transform(vec![frame], transform))
+	}
+
+	fn error(&self) -> Option<&str> {
+		self.error.as_deref()
+	}
+
+	fn take_view_commands(&mut self) -> Vec<ViewCommand> {
+		std::mem::take(&mut self.state.lock().unwrap().view_commands)
+	}
+}
+
+// a socket left by a viewer that didn't quit cleanly is replaced, the one of a viewer still listening and other files are not
+#[cfg(unix)]
+fn listen_unix(path: &str, state: &Arc<Mutex<ServerState>>) -> io::Result<()> {
+	use std::{fs, os::unix::{fs::FileTypeExt, net::{UnixListener, UnixStream}}};
+
+	if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+		match UnixStream::connect(path) {
+			Ok(_) => return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("another viewer is listening on '{}'", path))),
+			// no one accepts connections on it anymore
+			Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => fs::remove_file(path)?,
+			// binding fails with its own error
+			Err(_) => (),
+		}
+	}
+
+	let listener = UnixListener::bind(path)?;
+	let state = Arc::clone(state);
+	thread::spawn(move || accept_clients(listener.incoming(), &state));
+
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen_unix(_path: &str, _state: &Arc<Mutex<ServerState>>) -> io::Result<()> {
+	Err(io::Error::new(io::ErrorKind::Unsupported, "Unix domain sockets are not supported here, listen on a port"))
+}
+
+fn accept_clients<S: Send + 'static>(incoming: impl Iterator<Item = io::Result<S>>, state: &Arc<Mutex<ServerState>>)
+where
+	for<'a> &'a S: Read + Write
+{
+	// a client that failed to connect doesn't stop the others
+	for stream in incoming.flatten() {
+		let state = Arc::clone(state);
+		thread::spawn(move || serve_client(&stream, &state));
+	}
+}
+
+// until the client disconnects
+fn serve_client<S>(stream: &S, state: &Mutex<ServerState>)
+where
+	for<'a> &'a S: Read + Write
+{
+	let mut lines = BufReader::new(stream).lines();
+	let mut replies = stream;
+	// the header row of a frame is kept for the next ones, like in a file
+	let mut reader = YadeLineReader::default();
+
+	while let Some(Ok(line)) = lines.next() {
+		let result = match run_command(&line, &mut lines, &mut reader, state) {
+			Ok(false) => continue,
+			Ok(true) => Ok(()),
+			Err(err) => Err(err),
+		};
+
+		state.lock().unwrap().error = result.as_ref().err().cloned();
+
+		let reply = match result {
+			Ok(()) => "ok\n".to_owned(),
+			Err(err) => format!("error {}\n", err),
+		};
+		if replies.write_all(reply.as_bytes()).is_err() { return }
+	}
+}
+
+// false if the line is not a command
+fn run_command(line: &str, lines: &mut impl Iterator<Item = io::Result<String>>, reader: &mut YadeLineReader, state: &Mutex<ServerState>) -> Result<bool, String> {
+	let mut words = line.split_whitespace();
+	let command = match words.next() {
+		Some(command) if !command.starts_with('#') => command,
+		_ => return Ok(false),
+	};
+
+	match command {
+		"frame" => {
+			let iteration = next_value_or_none(&mut words, "an iteration").and_then(|iteration| expect_end(words).map(|_| iteration));
+
+			// even if the command is bad, so its lines are not taken as commands
+			let data = read_frame(lines, reader);
+			let (iteration, data) = (iteration?, data?);
+
+			let mut state = state.lock().unwrap();
+			state.data = data;
+			state.iteration = iteration;
+			state.has_changed = true;
+		}
+		"ball" => {
+			let index: usize = next_value(&mut words, "a ball index")?;
+			let pos = Vec3::new(next_value(&mut words, "x")?, next_value(&mut words, "y")?, next_value(&mut words, "z")?);
+			let rad = next_value_or_none(&mut words, "a radius")?;
+			expect_end(words)?;
+
+			let mut state = state.lock().unwrap();
+			let balls_count = state.data.balls.len();
+			let ball = state.data.balls.get_mut(index).ok_or_else(|| format!("there is no ball {}, the frame has {} balls", index, balls_count))?;
+
+			ball.pos = pos;
+			if let Some(rad) = rad {
+				ball.rad = rad;
+			}
+			state.has_changed = true;
+		}
+		"camera" => {
+			let view_command = match words.next() {
+				Some("reset") => ViewCommand::ResetCamera,
+				Some("orbit") => {
+					let rot_x: f32 = next_value(&mut words, "an angle around X, in degrees")?;
+					let rot_y: f32 = next_value(&mut words, "an angle around Y, in degrees")?;
+					let dist = next_value_or_none(&mut words, "a distance")?;
+					ViewCommand::OrbitCamera { rot_x: rot_x.to_radians(), rot_y: rot_y.to_radians(), dist }
+				}
+				_ => return Err("expected 'camera reset' or 'camera orbit <x> <y> [<distance>]'".to_owned()),
+			};
+			expect_end(words)?;
+
+			state.lock().unwrap().view_commands.push(view_command);
+		}
+		"screenshot" => {
+			expect_end(words)?;
+			state.lock().unwrap().view_commands.push(ViewCommand::Screenshot);
+		}
+		_ => return Err(format!("unknown command '{}', expected frame, ball, camera or screenshot", command)),
+	}
+
+	Ok(true)
+}
+
+// the lines up to "end", the rest of a frame is skipped after a bad line
+fn read_frame(lines: &mut impl Iterator<Item = io::Result<String>>, reader: &mut YadeLineReader) -> Result<YadeDemData, String> {
+	let mut frame_error = None;
+
+	for (line_index, line) in lines.enumerate() {
+		let line = line.map_err(|err| err.to_string())?;
+
+		if line.trim() == "end" {
+			let data = reader.take_frame();
+			return match frame_error {
+				Some(err) => Err(err),
+				None => Ok(data),
+			};
+		}
+
+		if frame_error.is_some() || line.trim().is_empty() { continue }
+
+		if let Err((msg, col, token)) = reader.read_line(&line) {
+			frame_error = Some(format!("line {} of the frame, column {}: {} '{}'", line_index + 1, col, msg, token));
+		}
+	}
+
+	Err("the frame has no 'end' line".to_owned())
+}
+
+fn next_value<T: FromStr>(words: &mut SplitWhitespace, expected: &str) -> Result<T, String> {
+	next_value_or_none(words, expected)?.ok_or_else(|| format!("missing {}", expected))
+}
+
+fn next_value_or_none<T: FromStr>(words: &mut SplitWhitespace, expected: &str) -> Result<Option<T>, String> {
+	match words.next() {
+		Some(word) => word.parse().map(Some).map_err(|_| format!("expected {}, found '{}'", expected, word)),
+		None => Ok(None),
+	}
+}
+
+fn expect_end(mut words: SplitWhitespace) -> Result<(), String> {
+	match words.next() {
+		Some(word) => Err(format!("unexpected '{}' after the command", word)),
+		None => Ok(()),
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use std::{f32::consts::{FRAC_PI_2, PI}, io::ErrorKind};
+
+	use crate::{file_readers::yade_dem_reader::Ball, scene_client::SceneClient};
+
+	use super::*;
+
+	// with no bodies, the transform is found from the first frame sent
+	fn listen_empty(address: &ListenAddress) -> io::Result<SceneServer> {
+		let convention = CoordinateConvention::default();
+		let data = YadeDemData::empty();
+		SceneServer::listen(address, data.clone(), WorldTransform::new(&convention, &data), convention)
+	}
+
+	fn start_server(address: &ListenAddress) -> (SceneServer, SceneClient) {
+		let server = listen_empty(address).unwrap();
+		let client = SceneClient::connect(&server.address).unwrap();
+		(server, client)
+	}
+
+	// any free port of localhost
+	fn start_tcp_server() -> (SceneServer, SceneClient) {
+		start_server(&ListenAddress::Tcp(0))
+	}
+
+	fn send_one_ball(client: &mut SceneClient) {
+		let ball = Ball { pos: Vec3::new(1.0, 2.0, 3.0), rad: 0.5, id: 0, attributes: vec![] };
+		client.send_balls(None, &[ball]).unwrap();
+	}
+
+	#[test]
+	fn frame_with_header_row_is_polled_once() {
+		let (mut server, mut client) = start_tcp_server();
+		assert!(server.poll().is_none());
+
+		client.send_frame_lines(Some(120), ["# type, x, y, z, r, vx", "0, 1, 2, 3, 0.5, 7", "0, 4, 5, 6, 0.25, 8"]).unwrap();
+
+		let timeline = server.poll().expect("the frame that was sent");
+		let frame = &timeline.frames[0];
+		assert_eq!(frame.iteration, Some(120));
+		assert_eq!(frame.data.attribute_names, ["vx"]);
+		assert_eq!(frame.data.balls.len(), 2);
+		assert_eq!(frame.data.balls[1].attributes, [8.0]);
+		assert!(server.error().is_none());
+
+		assert!(server.poll().is_none());
+	}
+
+	#[test]
+	fn ball_out_of_range_is_an_error() {
+		let (mut server, mut client) = start_tcp_server();
+		send_one_ball(&mut client);
+		assert!(server.poll().is_some());
+
+		client.move_ball(0, Vec3::new(1.0, 1.0, 1.0), Some(0.2)).unwrap();
+		assert!(server.poll().is_some());
+
+		let err = client.move_ball(3, Vec3::new(1.0, 1.0, 1.0), None).unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::InvalidInput);
+		assert_eq!(err.to_string(), "there is no ball 3, the frame has 1 balls");
+
+		assert!(server.poll().is_none());
+		assert_eq!(server.error(), Some("there is no ball 3, the frame has 1 balls"));
+	}
+
+	#[test]
+	fn bad_line_skips_the_rest_of_the_frame() {
+		let (mut server, mut client) = start_tcp_server();
+
+		let err = client.send_command("frame\n0, 1, x, 3, 0.5\nscreenshot\ncamera reset\nend").unwrap_err();
+		assert!(err.to_string().starts_with("line 1 of the frame, column 7:"), "{}", err);
+
+		// the lines after the bad one were not run as commands, and the next command gets its own reply
+		assert!(server.take_view_commands().is_empty());
+		client.reset_camera().unwrap();
+		assert!(matches!(server.take_view_commands()[..], [ViewCommand::ResetCamera]));
+
+		assert!(server.poll().is_none());
+	}
+
+	#[test]
+	fn camera_orbit_is_in_degrees() {
+		let (mut server, mut client) = start_tcp_server();
+
+		client.orbit_camera(90.0, 180.0, Some(10.0)).unwrap();
+		client.orbit_camera(0.0, 0.0, None).unwrap();
+
+		let view_commands = server.take_view_commands();
+		match view_commands[..] {
+			[ViewCommand::OrbitCamera { rot_x, rot_y, dist: Some(dist) }, ViewCommand::OrbitCamera { dist: None, .. }] => {
+				assert!((rot_x - FRAC_PI_2).abs() < 1e-6);
+				assert!((rot_y - PI).abs() < 1e-6);
+				assert_eq!(dist, 10.0);
+			}
+			_ => panic!("expected 2 orbits, got {} commands", view_commands.len()),
+		}
+
+		let err = client.send_command("camera orbit 1").unwrap_err();
+		assert_eq!(err.to_string(), "missing an angle around Y, in degrees");
+		assert!(server.take_view_commands().is_empty());
+	}
+
+	#[test]
+	fn screenshot_is_taken_by_the_render_loop() {
+		let (mut server, mut client) = start_tcp_server();
+
+		client.take_screenshot().unwrap();
+		assert!(matches!(server.take_view_commands()[..], [ViewCommand::Screenshot]));
+		assert!(server.take_view_commands().is_empty());
+
+		assert!(client.send_command("screenshot now").is_err());
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn unix_socket_is_removed_with_the_server() {
+		let path = std::env::temp_dir().join(format!("peekdem-test-{}.sock", std::process::id())).to_string_lossy().into_owned();
+		let address = ListenAddress::Unix(path.clone());
+
+		let (mut server, mut client) = start_server(&address);
+		// a second viewer doesn't take it
+		assert_eq!(listen_empty(&address).err().map(|err| err.kind()), Some(ErrorKind::AddrInUse));
+
+		send_one_ball(&mut client);
+		assert_eq!(server.poll().unwrap().frames[0].data.balls.len(), 1);
+
+		drop(server);
+		assert!(!std::path::Path::new(&path).exists());
+	}
+}
@@ -1,16 +1,45 @@
-use std::process;
+use std::{env, path::Path, process};
 
+use crossterm::style::available_color_count;
+
+use crate::{file_readers::{coordinate_convention::{CoordinateConvention, Handedness, UnitScale, UpAxis}, file_format::FileFormat, sphere_list_reader::SphereListOptions, yade_interactions::ForceThresholds}, scene_server::ListenAddress};
+
+#[derive(Clone)]
 pub enum ControlMode {
 	Automatic,
 	Wasd,
 }
 
+#[derive(Clone)]
 pub struct Settings {
 	pub has_custom_path: bool,
 	pub custom_path: String,
 	pub draw_normals: bool,
 	pub draw_wireframe: bool,
 	pub control_mode: ControlMode,
+	pub use_color: bool,
+	// None guesses it from the file content
+	pub format: Option<FileFormat>,
+	// keeps reading after a bad line to report all of them
+	pub report_all_errors: bool,
+	// up axis, handedness and scale of YADE input
+	pub coordinate_convention: CoordinateConvention,
+	// YADE contacts file (or sequence) drawn over the bodies
+	pub interactions_path: Option<String>,
+	pub force_thresholds: ForceThresholds,
+	// delimiter, columns and skipped lines of sphere lists
+	pub sphere_list: SphereListOptions,
+	// reads and writes the binary cache next to the input file
+	pub use_cache: bool,
+	// "peekdem convert <input> [<output>]" writes the binary cache of the input and quits
+	pub convert: bool,
+	pub convert_output: Option<String>,
+	// reads the input file again when it changes on disk
+	pub hot_reload: bool,
+	// takes scene updates from simulation scripts on a socket or port, instead of watching the file
+	pub listen: Option<ListenAddress>,
+	// "peekdem send <socket path | port> <file>" pushes a YADE snapshot to a viewer listening there and quits
+	pub send_address: Option<ListenAddress>,
 }
 
 impl Settings {
@@ -24,11 +53,26 @@ impl Settings {
 			draw_normals: false,
 			draw_wireframe: false,
 			control_mode: ControlMode::Wasd,
+			use_color: Self::is_color_available(),
+			format: None,
+			report_all_errors: false,
+			coordinate_convention: CoordinateConvention::default(),
+			interactions_path: None,
+			force_thresholds: ForceThresholds::default(),
+			sphere_list: SphereListOptions::default(),
+			use_cache: true,
+			convert: false,
+			convert_output: None,
+			hot_reload: true,
+			listen: None,
+			send_address: None,
 		};
 
-		for arg in args {
+		let mut args = args;
+		while let Some(arg) = args.next() {
 
-			let is_option = arg.as_str().starts_with('-');
+			// "-" is stdin
+			let is_option = arg.as_str().starts_with('-') && arg != "-";
 			if is_option {
 				match arg.as_str() {
 					"-n" | "--normal" | "--normals" => {
@@ -40,6 +84,68 @@ impl Settings {
 					"-w" | "--wire" | "--wireframe" => {
 						settings.draw_wireframe = true;
 					}
+					"--color" => {
+						settings.use_color = true;
+					}
+					"--no-color" => {
+						settings.use_color = false;
+					}
+					"-f" | "--format" => {
+						let format_name = Self::next_option_value(&mut args, &arg, &FileFormat::list_names());
+						settings.format = Some(Self::parse_format(&format_name));
+					}
+					_ if arg.starts_with("--format=") => {
+						settings.format = Some(Self::parse_format(&arg["--format=".len()..]));
+					}
+					"--all-errors" => {
+						settings.report_all_errors = true;
+					}
+					"--up" => {
+						let up_str = Self::next_option_value(&mut args, &arg, "x, y, z");
+						settings.coordinate_convention.up_axis = Self::parse_option_value(&arg, &up_str, UpAxis::from_name, "x, y, z");
+					}
+					"--handedness" => {
+						let handedness_str = Self::next_option_value(&mut args, &arg, "right, left");
+						settings.coordinate_convention.handedness = Self::parse_option_value(&arg, &handedness_str, Handedness::from_name, "right, left");
+					}
+					"--scale" => {
+						let scale_str = Self::next_option_value(&mut args, &arg, "a positive number, auto");
+						settings.coordinate_convention.scale = Self::parse_option_value(&arg, &scale_str, UnitScale::from_name, "a positive number, auto");
+					}
+					"-i" | "--interactions" => {
+						settings.interactions_path = Some(Self::next_option_value(&mut args, &arg, "a path"));
+					}
+					"--min-force" => {
+						let force_str = Self::next_option_value(&mut args, &arg, "a force, 0 or more");
+						settings.force_thresholds.min = Self::parse_option_value(&arg, &force_str, Self::parse_force, "a force, 0 or more");
+					}
+					"--max-force" => {
+						let force_str = Self::next_option_value(&mut args, &arg, "a force, more than 0");
+						let max_force = Self::parse_option_value(&arg, &force_str, |value| Self::parse_force(value).filter(|force| *force > 0.0), "a force, more than 0");
+						settings.force_thresholds.max = Some(max_force);
+					}
+					"--delimiter" => {
+						let delimiter_str = Self::next_option_value(&mut args, &arg, "a single char, tab, space");
+						settings.sphere_list.delimiter = Some(Self::parse_option_value(&arg, &delimiter_str, SphereListOptions::delimiter_from_name, "a single char, tab, space"));
+					}
+					"--columns" => {
+						let columns_str = Self::next_option_value(&mut args, &arg, "column names, like x,y,z,r,mass");
+						settings.sphere_list.column_names = Some(Self::parse_option_value(&arg, &columns_str, SphereListOptions::column_names_from_list, "column names, like x,y,z,r,mass"));
+					}
+					"--skip-lines" => {
+						let skip_str = Self::next_option_value(&mut args, &arg, "a number of lines");
+						settings.sphere_list.skip_lines = Self::parse_option_value(&arg, &skip_str, |value| value.parse().ok(), "a number of lines");
+					}
+					"--no-cache" => {
+						settings.use_cache = false;
+					}
+					"--no-reload" => {
+						settings.hot_reload = false;
+					}
+					"--listen" => {
+						let address_str = Self::next_option_value(&mut args, &arg, "a socket path, a port");
+						settings.listen = Some(Self::parse_option_value(&arg, &address_str, ListenAddress::from_arg, "a socket path, a port"));
+					}
 					_ => {
 						let chars_after_slash = arg.chars().skip(1);
 						println!("Unknown option -- {}", String::from_iter(chars_after_slash));
@@ -50,6 +156,25 @@ impl Settings {
 				continue;
 			}
 
+			// a file named "convert" can still be opened
+			let is_convert_command = !settings.has_custom_path && !settings.convert && arg == "convert" && !Path::new(&arg).exists();
+			if is_convert_command {
+				settings.convert = true;
+				continue;
+			}
+
+			let is_send_command = !settings.has_custom_path && !settings.convert && settings.send_address.is_none() && arg == "send" && !Path::new(&arg).exists();
+			if is_send_command {
+				let address_str = Self::next_option_value(&mut args, &arg, "a socket path, a port");
+				settings.send_address = Some(Self::parse_option_value(&arg, &address_str, ListenAddress::from_arg, "a socket path, a port"));
+				continue;
+			}
+
+			if settings.has_custom_path && settings.convert && settings.convert_output.is_none() {
+				settings.convert_output = Some(arg);
+				continue;
+			}
+
 			if settings.has_custom_path {
 				println!("Too many arguments!");
 				process::exit(1);
@@ -61,4 +186,46 @@ impl Settings {
 
 		settings
 	}
-}
\ No newline at end of file
+
+	fn parse_format(format_name: &str) -> FileFormat {
+		match FileFormat::from_name(format_name) {
+			Some(format) => format,
+			None => {
+				println!("Unknown format '{}', supported formats: {}", format_name, FileFormat::list_names());
+				process::exit(1);
+			}
+		}
+	}
+
+	// the argument after an option like "--scale 10", 'expected' lists the valid values
+	fn next_option_value(args: &mut impl Iterator<Item = String>, option: &str, expected: &str) -> String {
+		match args.next() {
+			Some(value) => value,
+			None => {
+				println!("Missing value after {}, expected: {}", option, expected);
+				process::exit(1);
+			}
+		}
+	}
+
+	fn parse_option_value<T>(option: &str, value: &str, parse: fn(&str) -> Option<T>, expected: &str) -> T {
+		match parse(value) {
+			Some(parsed) => parsed,
+			None => {
+				println!("Invalid value '{}' for {}, expected: {}", value, option, expected);
+				process::exit(1);
+			}
+		}
+	}
+
+	fn parse_force(value: &str) -> Option<f32> {
+		value.parse::<f32>().ok().filter(|force| *force >= 0.0)
+	}
+
+	// https://no-color.org
+	fn is_color_available() -> bool {
+		if env::var_os("NO_COLOR").is_some() { return false }
+
+		available_color_count() >= 256 || env::var_os("COLORTERM").is_some()
+	}
+}
@@ -3,6 +3,9 @@ use std::{fmt::Debug, fs::File, io::{BufWriter, Write}};
 use crate::{cull_mode::CullMode, maths::*, render_clear, render_settings::*, ASCII_BYTES_PER_CHAR};
 
 type DebugFile = File;
+
+// ANSI 0 is black, so it's used to mean "no color" (the terminal's default)
+pub const NO_COLOR: u8 = 0;
 // type DebugFile = BufWriter<File>;
 
 pub struct TerminalBuffer {
@@ -12,6 +15,13 @@ pub struct TerminalBuffer {
 
 	// global output buffer
 	pub raw_ascii_screen: Vec<u8>,
+	// ANSI 256 color of each char in 'raw_ascii_screen', NO_COLOR means the terminal's default
+	pub raw_color_screen: Vec<u8>,
+	// depth of what was drawn in each char of 'raw_ascii_screen' by the depth tested primitives, smaller is closer
+	pub depth_screen: Vec<f32>,
+	// color written along with every char, only printed if 'use_color' is set
+	pub fill_color: u8,
+	pub use_color: bool,
 
 	// unique 4x4 matrix buffers, reused across different rendered objects, mut be cleaned after each use
 	proj_mat: Vec<f32>,
@@ -21,7 +31,10 @@ pub struct TerminalBuffer {
 	sorting_mode:   ZSortingMode,
 	cull_mask:      CullMode,
 	ball_fill_mode: BallFillMode,
+	ball_shading_mode: BallShadingMode,
 	gizmos_mode:    GizmosType,
+	mesh_fill_mode: MeshFillMode,
+	mesh_shading_mode: MeshShadingMode,
 
 	debug_file: Option<DebugFile>,
 
@@ -41,6 +54,10 @@ impl TerminalBuffer {
 			wid: w,
 			hei: h,
 			raw_ascii_screen: vec![0; char_len],
+			raw_color_screen: vec![NO_COLOR; w as usize * h as usize],
+			depth_screen: vec![f32::INFINITY; w as usize * h as usize],
+			fill_color: NO_COLOR,
+			use_color: false,
 
 			proj_mat:   create_identity_4x4(),
 			transf_mat: create_identity_4x4(),
@@ -49,7 +66,10 @@ impl TerminalBuffer {
     		sorting_mode:   ZSortingMode::Optimized,
 			cull_mask:      CullMode::Nothing,
 			ball_fill_mode: BallFillMode::Index,
+			ball_shading_mode: BallShadingMode::Spheres,
 			gizmos_mode:    GizmosType::None,
+			mesh_fill_mode: MeshFillMode::Solid,
+			mesh_shading_mode: MeshShadingMode::Gouraud,
 
 			debug_file,
 			test: false,
@@ -73,10 +93,22 @@ impl TerminalBuffer {
 		&self.ball_fill_mode
 	}
 
+	pub fn get_ball_shading_mode(&self) -> &BallShadingMode {
+		&self.ball_shading_mode
+	}
+
 	pub fn get_gizmos_mode(&self) -> &GizmosType {
 		&self.gizmos_mode
 	}
 
+	pub fn get_mesh_fill_mode(&self) -> &MeshFillMode {
+		&self.mesh_fill_mode
+	}
+
+	pub fn get_mesh_shading_mode(&self) -> &MeshShadingMode {
+		&self.mesh_shading_mode
+	}
+
 	fn open_and_clear_debug_file() -> Option<DebugFile> {
 		File::create(Self::DEBUG_FILE_PATH).ok()
 		// File::create(Self::DEBUG_FILE_PATH).map(BufWriter::new).ok()
@@ -90,6 +122,10 @@ impl TerminalBuffer {
 		let char_len = w as usize * h as usize * ASCII_BYTES_PER_CHAR;
 		self.raw_ascii_screen.clear();
 		self.raw_ascii_screen.resize(char_len, 0);
+		self.raw_color_screen.clear();
+		self.raw_color_screen.resize(w as usize * h as usize, NO_COLOR);
+		self.depth_screen.clear();
+		self.depth_screen.resize(w as usize * h as usize, f32::INFINITY);
 
 		render_clear(self);
 	}
@@ -193,15 +229,24 @@ impl TerminalBuffer {
 		self.ball_fill_mode = match self.ball_fill_mode {
 			BallFillMode::Height     => BallFillMode::XZDistance,
 			BallFillMode::XZDistance => BallFillMode::Index,
-			BallFillMode::Index      => BallFillMode::Height,
+			BallFillMode::Index      => BallFillMode::Attribute,
+			BallFillMode::Attribute  => BallFillMode::Height,
 		}
 	}
 
 	pub fn toggle_back_ball_fill_mode(&mut self) {
 		self.ball_fill_mode = match self.ball_fill_mode {
-			BallFillMode::Height     => BallFillMode::Index,
+			BallFillMode::Height     => BallFillMode::Attribute,
 			BallFillMode::XZDistance => BallFillMode::Height,
 			BallFillMode::Index      => BallFillMode::XZDistance,
+			BallFillMode::Attribute  => BallFillMode::Index,
+		}
+	}
+
+	pub fn toggle_ball_shading_mode(&mut self) {
+		self.ball_shading_mode = match self.ball_shading_mode {
+			BallShadingMode::Spheres => BallShadingMode::Discs,
+			BallShadingMode::Discs   => BallShadingMode::Spheres,
 		}
 	}
 
@@ -213,6 +258,21 @@ impl TerminalBuffer {
 	}
 
 
+	pub fn toggle_mesh_fill_mode(&mut self) {
+		self.mesh_fill_mode = match self.mesh_fill_mode {
+			MeshFillMode::Solid     => MeshFillMode::Wireframe,
+			MeshFillMode::Wireframe => MeshFillMode::Solid,
+		}
+	}
+
+	pub fn toggle_mesh_shading_mode(&mut self) {
+		self.mesh_shading_mode = match self.mesh_shading_mode {
+			MeshShadingMode::Flat    => MeshShadingMode::Gouraud,
+			MeshShadingMode::Gouraud => MeshShadingMode::Flat,
+		}
+	}
+
+
 	const DEBUG_FILE_PATH: &str = "bullshit/_debug.txt";
 	pub fn clear_debug(&mut self) {	
 		self.debug_file = Self::open_and_clear_debug_file();
@@ -11,7 +11,7 @@ pub struct CrosstermTerminal {
 	// pub stdout: BufWriter<File>,
 }
 
-use crate::{maths::*, render_clear, render_help_screen, render_string_snap_right, timer::Timer, App, TerminalBuffer};
+use crate::{maths::*, scene_server::remove_unix_sockets, terminal::NO_COLOR, render_clear, render_help_screen, render_string_snap_right, timer::Timer, App, TerminalBuffer};
 
 
 pub fn configure_terminal() -> CrosstermTerminal {
@@ -156,7 +156,27 @@ pub fn poll_events(terminal: &mut CrosstermTerminal, app: &mut App, timer: &mut
 					'c' => app.buf.toggle_cull_mode(),
 					'l' if key_evt.modifiers == KeyModifiers::SHIFT => app.buf.toggle_back_ball_fill_mode(),
 					'l' => app.buf.toggle_ball_fill_mode(),
+					// U toggles balls between shaded spheres and flat discs
+					'u' => app.buf.toggle_ball_shading_mode(),
 					'g' => app.buf.toggle_gizmos_mode(),
+					// K toggles meshes between solid and wireframe
+					'k' => app.buf.toggle_mesh_fill_mode(),
+					// J toggles the shading of solid meshes between flat and gouraud
+					'j' => app.buf.toggle_mesh_shading_mode(),
+					// B selects the next YADE attribute to light balls by, shift+B the previous one
+					'b' if key_evt.modifiers == KeyModifiers::SHIFT => app.called_select_prev_attribute = true,
+					'b' => app.called_select_next_attribute = true,
+					// F hides weaker YADE contacts, shift+F shows them again
+					'f' if key_evt.modifiers == KeyModifiers::SHIFT => app.called_lower_force_threshold = true,
+					'f' => app.called_raise_force_threshold = true,
+
+					// N selects the next mesh part, shift+N the previous one, X hides / shows the selected part
+					'n' if key_evt.modifiers == KeyModifiers::SHIFT => app.called_select_prev_part = true,
+					'n' => app.called_select_next_part = true,
+					'x' => app.called_toggle_part_visibility = true,
+					// . / , steps a YADE timeline one snapshot forwards / backwards
+					'.' => app.called_step_frame_forward = true,
+					',' => app.called_step_frame_back = true,
 					'm' => app.called_toggle_free_mov = true,
 					'v' => app.is_verbose = !app.is_verbose,
 
@@ -256,14 +276,52 @@ pub fn print_and_flush_terminal_line_by_line(buf: &mut TerminalBuffer, terminal:
 	terminal.stdout.flush().unwrap();
 }
 
+// line by line too, changes the foreground color only when it differs from the previous char's
+pub fn print_and_flush_terminal_color(buf: &mut TerminalBuffer, terminal: &mut CrosstermTerminal) {
+
+	let buf_wid = buf.wid as usize;
+	let mut last_color = NO_COLOR;
+	queue!(terminal.stdout, Hide, ResetColor).unwrap();
+
+	for y in 0..buf.hei {
+
+		let y_start = y as usize * buf_wid;
+		let y_end   = y_start + buf_wid;
+
+		queue!(terminal.stdout, MoveTo(0, y)).unwrap();
+
+		let mut run_start = y_start;
+		while run_start < y_end {
+			let color = buf.raw_color_screen[run_start];
+			let run_end = (run_start..y_end).find(|&i| buf.raw_color_screen[i] != color).unwrap_or(y_end);
+
+			if color != last_color {
+				let fg_color = if color == NO_COLOR { Color::Reset } else { Color::AnsiValue(color) };
+				queue!(terminal.stdout, SetForegroundColor(fg_color)).unwrap();
+				last_color = color;
+			}
+
+			let buf_str = std::str::from_utf8(&buf.raw_ascii_screen[run_start .. run_end]).unwrap();
+			queue!(terminal.stdout, Print(buf_str)).unwrap();
+
+			run_start = run_end;
+		}
+	}
+
+	queue!(terminal.stdout, ResetColor).unwrap();
+	terminal.stdout.flush().unwrap();
+}
+
 
 fn quit(terminal: &mut CrosstermTerminal) {
 	restore_terminal(terminal);
+	remove_unix_sockets();
 	process::exit(0);
 }
 
 fn quit_with_message(terminal: &mut CrosstermTerminal, message: &str) {
 	restore_terminal(terminal);
+	remove_unix_sockets();
 	println!("{message}");
 	process::exit(0);
 }